@@ -0,0 +1,66 @@
+// one proof over n circuit copies against n separate proofs
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use halo2_example::{
+    example4::AddCircuit,
+    prover::{self, Instances},
+};
+use halo2_proofs::{circuit::Value, pasta::Fp};
+
+fn add_circuits(n: u64) -> (Vec<AddCircuit<Fp>>, Instances) {
+    (0..n)
+        .map(|i| {
+            let (a, b) = (Fp::from(i), Fp::from(2 * i + 1));
+            let circuit = AddCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+            (circuit, vec![vec![a, b, a + b]])
+        })
+        .unzip()
+}
+
+fn multi(c: &mut Criterion) {
+    let (params, pk) = prover::setup(4, &AddCircuit::<Fp>::default()).unwrap();
+
+    let mut group = c.benchmark_group("prove");
+    group.sample_size(10);
+    for n in [1, 2, 16] {
+        let (circuits, instances) = add_circuits(n);
+
+        let multi = prover::prove_multi(&params, &pk, &circuits, &instances).unwrap();
+        let separate: usize = (0..circuits.len())
+            .map(|i| {
+                let circuits = &circuits[i..i + 1];
+                let instances = &instances[i..i + 1];
+                prover::prove_multi(&params, &pk, circuits, instances)
+                    .unwrap()
+                    .len()
+            })
+            .sum();
+        println!(
+            "n = {:>2}: one proof {} bytes, {} separate proofs {} bytes",
+            n,
+            multi.len(),
+            n,
+            separate
+        );
+
+        group.throughput(Throughput::Elements(n));
+        group.bench_with_input(BenchmarkId::new("multi", n), &n, |b, _| {
+            b.iter(|| prover::prove_multi(&params, &pk, &circuits, &instances).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("separate", n), &n, |b, _| {
+            b.iter(|| {
+                for i in 0..circuits.len() {
+                    let circuits = &circuits[i..i + 1];
+                    let instances = &instances[i..i + 1];
+                    prover::prove_multi(&params, &pk, circuits, instances).unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, multi);
+criterion_main!(benches);
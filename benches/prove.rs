@@ -0,0 +1,110 @@
+// keygen, proving and verification time across k, under SimpleFloorPlanner and V1
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_example::{
+    circuits, example4::AddCircuit, example5::Add2Circuit, planner::WithPlanner, prover, sizing,
+    test_fibonacci::MyCircuit,
+};
+use halo2_proofs::{
+    circuit::{floor_planner::V1, SimpleFloorPlanner, Value},
+    pasta::Fp,
+    plonk::{keygen_pk, keygen_vk, Circuit},
+    poly::commitment::Params,
+};
+
+// k above the minimum only adds unused rows, which is what the sizes measure
+const EXTRA_K: [u32; 3] = [0, 2, 4];
+
+fn bench_circuit<C: Circuit<Fp>>(c: &mut Criterion, name: &str, circuit: C, instances: Vec<Fp>) {
+    let min_k = sizing::min_k(&circuit, std::slice::from_ref(&instances)).unwrap();
+    let instances: &[&[Fp]] = &[&instances];
+
+    for k in EXTRA_K.iter().map(|extra| min_k + extra) {
+        let params: Params<_> = Params::new(k);
+        let empty = circuit.without_witnesses();
+        let vk = keygen_vk(&params, &empty).unwrap();
+        let pk = keygen_pk(&params, vk.clone(), &empty).unwrap();
+        let proof =
+            prover::prove(&params, &pk, std::slice::from_ref(&circuit), &[instances]).unwrap();
+
+        c.benchmark_group("keygen_vk")
+            .sample_size(10)
+            .bench_function(BenchmarkId::new(name, k), |b| {
+                b.iter(|| keygen_vk(&params, &empty).unwrap())
+            });
+        c.benchmark_group("keygen_pk")
+            .sample_size(10)
+            .bench_function(BenchmarkId::new(name, k), |b| {
+                b.iter(|| keygen_pk(&params, vk.clone(), &empty).unwrap())
+            });
+        c.benchmark_group("create_proof")
+            .sample_size(10)
+            .bench_function(BenchmarkId::new(name, k), |b| {
+                b.iter(|| {
+                    prover::prove(&params, &pk, std::slice::from_ref(&circuit), &[instances])
+                        .unwrap()
+                })
+            });
+        c.benchmark_group("verify_proof")
+            .sample_size(10)
+            .bench_function(BenchmarkId::new(name, k), |b| {
+                b.iter(|| prover::verify(&params, pk.get_vk(), &[instances], &proof).unwrap())
+            });
+    }
+}
+
+// the same circuit under both floor planners
+fn bench_planners<C: Circuit<Fp>>(
+    c: &mut Criterion,
+    name: &str,
+    circuit: impl Fn() -> C,
+    instances: Vec<Fp>,
+) {
+    bench_circuit(
+        c,
+        &format!("{}/simple", name),
+        WithPlanner::<_, SimpleFloorPlanner>::new(circuit()),
+        instances.clone(),
+    );
+    bench_circuit(
+        c,
+        &format!("{}/v1", name),
+        WithPlanner::<_, V1>::new(circuit()),
+        instances,
+    );
+}
+
+fn prove(c: &mut Criterion) {
+    let (a, b, c_) = (Fp::from(2), Fp::from(3), Fp::from(4));
+
+    bench_planners(
+        c,
+        "add",
+        || AddCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        },
+        vec![a, b, a + b],
+    );
+    bench_planners(
+        c,
+        "add2",
+        || Add2Circuit {
+            a: Value::known(a),
+            b: Value::known(b),
+            c: Value::known(c_),
+        },
+        vec![a, b, a + b, a + b + c_],
+    );
+    bench_planners(
+        c,
+        "fibonacci",
+        || MyCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        },
+        vec![a, b, circuits::fibonacci(a, b, 9)],
+    );
+}
+
+criterion_group!(benches, prove);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+// throughput of batch verification against one SingleVerifier per proof
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use halo2_example::{
+    example4::AddCircuit,
+    prover::{self, Instances},
+    transcript::Blake2b,
+};
+use halo2_proofs::{circuit::Value, pasta::Fp};
+
+fn verify(c: &mut Criterion) {
+    let (params, pk) = prover::setup(4, &AddCircuit::<Fp>::default()).unwrap();
+
+    let proofs: Vec<(Instances, Vec<u8>)> = (0..32u64)
+        .map(|i| {
+            let (a, b) = (Fp::from(i), Fp::from(i + 1));
+            let circuit = AddCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+            let public_input = vec![a, b, a + b];
+            let proof = prover::prove(&params, &pk, &[circuit], &[&[&public_input]]).unwrap();
+            (vec![vec![public_input]], proof)
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("verify");
+    for n in [1, 4, 16, 32] {
+        let proofs = &proofs[..n];
+        group.throughput(Throughput::Elements(n as u64));
+
+        group.bench_with_input(BenchmarkId::new("single", n), proofs, |b, proofs| {
+            b.iter(|| {
+                for (instances, proof) in proofs {
+                    prover::with_instance_slices(instances, |instances| {
+                        prover::verify(&params, pk.get_vk(), instances, proof).unwrap()
+                    });
+                }
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("batch", n), proofs, |b, proofs| {
+            b.iter(|| prover::verify_batch::<Blake2b>(&params, pk.get_vk(), proofs).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, verify);
+criterion_main!(benches);
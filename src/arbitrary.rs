@@ -0,0 +1,69 @@
+// proptest strategies and checks shared by the chip tests. Field elements lean
+// towards the edges: zero, small values and values just below the modulus,
+// besides uniform ones. Each is built from integers, so failures shrink towards
+// 0 and p - 1
+use halo2_proofs::{arithmetic::FieldExt, pasta::Fp, plonk::Circuit};
+use proptest::{prelude::*, test_runner::Config};
+
+use crate::{diagnostics, prover, sizing, underconstrained};
+
+pub fn config() -> Config {
+    // every case runs MockProver
+    Config::with_cases(64)
+}
+
+pub fn fp() -> impl Strategy<Value = Fp> {
+    prop_oneof![
+        Just(Fp::zero()),
+        any::<u64>().prop_map(Fp::from),
+        // p - 1 - n
+        any::<u64>().prop_map(|n| -Fp::from(n) - Fp::one()),
+        any::<[u8; 32]>().prop_map(|bytes| {
+            let mut wide = [0u8; 64];
+            wide[..32].copy_from_slice(&bytes);
+            Fp::from_bytes_wide(&wide)
+        }),
+    ]
+}
+
+// an offset that changes whatever it is added to
+pub fn nonzero() -> impl Strategy<Value = Fp> {
+    fp().prop_filter("offset must be nonzero", |d| *d != Fp::zero())
+}
+
+pub fn satisfied<C: Circuit<Fp>>(circuit: &C, instances: Vec<Vec<Fp>>) -> bool {
+    let k = sizing::min_k(circuit, &instances).unwrap();
+    diagnostics::check(k, circuit, instances).unwrap().is_ok()
+}
+
+// the soundness checks every chip runs against its honest `public_input`: the
+// circuit accepts it, rejects it with `delta` added to any one public value, and
+// rejects any single advice cell of its witness being changed
+pub fn assert_sound<C: Circuit<Fp>>(circuit: &C, public_input: Vec<Vec<Fp>>, delta: Fp) {
+    assert!(satisfied(circuit, public_input.clone()));
+    for (column, values) in public_input.iter().enumerate() {
+        for row in 0..values.len() {
+            let mut perturbed = public_input.clone();
+            perturbed[column][row] += delta;
+            assert!(
+                !satisfied(circuit, perturbed),
+                "instance column {} row {} is not bound",
+                column,
+                row
+            );
+        }
+    }
+
+    let analysis = underconstrained::analyze(circuit, public_input).unwrap();
+    assert!(analysis.is_ok(), "{}", analysis);
+}
+
+// keygen, prove and verify one copy of `circuit` at the smallest k it fits in
+pub fn proves<C: Circuit<Fp>>(circuit: C, instances: &[Vec<Fp>]) -> bool {
+    let (_, params, pk) = prover::setup_min_k(&circuit, instances).unwrap();
+
+    prover::with_instance_slices(&[instances.to_vec()], |instances| {
+        let proof = prover::prove(&params, &pk, &[circuit], instances).unwrap();
+        prover::verify(&params, pk.get_vk(), instances, &proof).is_ok()
+    })
+}
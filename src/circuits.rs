@@ -0,0 +1,522 @@
+// name -> circuit registry, so tools can drive the examples without knowing their types
+use std::{collections::BTreeMap, fmt};
+
+use halo2_proofs::{pasta::Fp, plonk::Circuit};
+
+use crate::input::{
+    Add2Input, AddColumnsInput, AddInput, CircuitInput, EqualityColumnsInput, EqualityInput,
+    FibonacciInput,
+};
+
+pub type Inputs = BTreeMap<String, Fp>;
+
+pub struct CircuitInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    // private witness values, in the order the circuit takes them. Taken from the
+    // circuit's input type, which `visit_input` picks by the same name
+    pub inputs: &'static [&'static str],
+}
+
+pub const CIRCUITS: &[CircuitInfo] = &[
+    CircuitInfo {
+        name: "add",
+        description: "a + b = res, public a, b, res in one instance column (example4)",
+        inputs: AddInput::INPUTS,
+    },
+    CircuitInfo {
+        name: "add-columns",
+        description: "a + b = res, one instance column per public value (example1)",
+        inputs: AddColumnsInput::INPUTS,
+    },
+    CircuitInfo {
+        name: "equality",
+        description: "input == output, both public in one instance column (example3)",
+        inputs: EqualityInput::INPUTS,
+    },
+    CircuitInfo {
+        name: "equality-columns",
+        description: "input == output, one instance column per public value (example2)",
+        inputs: EqualityColumnsInput::INPUTS,
+    },
+    CircuitInfo {
+        name: "add2",
+        description: "a + b + c = res using two AddChip rows (example5)",
+        inputs: Add2Input::INPUTS,
+    },
+    CircuitInfo {
+        name: "fibonacci",
+        description: "10th fibonacci number from a, b (test_fibonacci)",
+        inputs: FibonacciInput::INPUTS,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static CircuitInfo> {
+    CIRCUITS.iter().find(|info| info.name == name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CircuitError {
+    UnknownCircuit(String),
+    MissingInput { circuit: String, input: String },
+    UnexpectedInput { circuit: String, input: String },
+}
+
+impl fmt::Display for CircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitError::UnknownCircuit(name) => write!(f, "unknown circuit {:?}", name),
+            CircuitError::MissingInput { circuit, input } => {
+                write!(f, "circuit {:?} needs input {:?}", circuit, input)
+            }
+            CircuitError::UnexpectedInput { circuit, input } => {
+                write!(f, "circuit {:?} has no input {:?}", circuit, input)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CircuitError {}
+
+// called back with the concrete circuit type behind a name
+pub trait CircuitVisitor {
+    type Output;
+
+    // `instances` holds one vec per instance column
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output;
+}
+
+// called back with the typed input of the circuit behind a name
+pub trait InputVisitor {
+    type Output;
+
+    fn visit<I: CircuitInput>(self) -> Self::Output;
+}
+
+// the one place a name picks a circuit: everything else goes through its input type
+pub fn visit_input<V: InputVisitor>(name: &str, visitor: V) -> Result<V::Output, CircuitError> {
+    let info = find(name).ok_or_else(|| CircuitError::UnknownCircuit(name.to_string()))?;
+
+    Ok(match info.name {
+        "add" => visitor.visit::<AddInput>(),
+        "add-columns" => visitor.visit::<AddColumnsInput>(),
+        "equality" => visitor.visit::<EqualityInput>(),
+        "equality-columns" => visitor.visit::<EqualityColumnsInput>(),
+        "add2" => visitor.visit::<Add2Input>(),
+        "fibonacci" => visitor.visit::<FibonacciInput>(),
+        _ => unreachable!("every registered circuit is handled"),
+    })
+}
+
+struct FromInputs<'a, V> {
+    name: &'a str,
+    inputs: &'a Inputs,
+    visitor: V,
+}
+
+impl<V: CircuitVisitor> InputVisitor for FromInputs<'_, V> {
+    type Output = Result<V::Output, CircuitError>;
+
+    fn visit<I: CircuitInput>(self) -> Self::Output {
+        for input in self.inputs.keys() {
+            if !I::INPUTS.contains(&input.as_str()) {
+                return Err(CircuitError::UnexpectedInput {
+                    circuit: self.name.to_string(),
+                    input: input.clone(),
+                });
+            }
+        }
+
+        let values = I::INPUTS
+            .iter()
+            .map(|input| {
+                self.inputs
+                    .get(*input)
+                    .copied()
+                    .ok_or_else(|| CircuitError::MissingInput {
+                        circuit: self.name.to_string(),
+                        input: input.to_string(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let input = I::from_values(&values);
+        Ok(self.visitor.visit(input.circuit(), input.public_inputs()))
+    }
+}
+
+// builds the named circuit with `inputs` as witness and the public inputs it implies
+pub fn visit<V: CircuitVisitor>(
+    name: &str,
+    inputs: &Inputs,
+    visitor: V,
+) -> Result<V::Output, CircuitError> {
+    visit_input(
+        name,
+        FromInputs {
+            name,
+            inputs,
+            visitor,
+        },
+    )?
+}
+
+struct Given<V> {
+    instances: Vec<Vec<Fp>>,
+    visitor: V,
+}
+
+impl<V: CircuitVisitor> CircuitVisitor for Given<V> {
+    type Output = V::Output;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, _: Vec<Vec<Fp>>) -> Self::Output {
+        self.visitor.visit(circuit, self.instances)
+    }
+}
+
+// `visit` with `instances`, when given, in place of the public inputs `inputs` imply
+pub fn visit_with<V: CircuitVisitor>(
+    name: &str,
+    inputs: &Inputs,
+    instances: Option<Vec<Vec<Fp>>>,
+    visitor: V,
+) -> Result<V::Output, CircuitError> {
+    match instances {
+        Some(instances) => visit(name, inputs, Given { instances, visitor }),
+        None => visit(name, inputs, visitor),
+    }
+}
+
+struct PublicInputs;
+
+impl CircuitVisitor for PublicInputs {
+    type Output = Vec<Vec<Fp>>;
+
+    fn visit<C: Circuit<Fp>>(self, _: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+        instances
+    }
+}
+
+// the instance columns `inputs` imply for the named circuit
+pub fn instances(name: &str, inputs: &Inputs) -> Result<Vec<Vec<Fp>>, CircuitError> {
+    visit(name, inputs, PublicInputs)
+}
+
+struct Shape<V>(V);
+
+impl<V: CircuitVisitor> InputVisitor for Shape<V> {
+    type Output = V::Output;
+
+    fn visit<I: CircuitInput>(self) -> Self::Output {
+        self.0.visit(I::Circuit::default(), vec![])
+    }
+}
+
+// same as `visit` without a witness, for keygen and verification
+pub fn visit_shape<V: CircuitVisitor>(name: &str, visitor: V) -> Result<V::Output, CircuitError> {
+    visit_input(name, Shape(visitor))
+}
+
+// n-th element of the fibonacci sequence starting at a, b
+pub fn fibonacci(a: Fp, b: Fp, n: usize) -> Fp {
+    let (mut a, mut b) = (a, b);
+    for _ in 0..n {
+        let c = a + b;
+        a = b;
+        b = c;
+    }
+    a
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp, plonk::Circuit};
+
+    use super::{
+        fibonacci, visit, visit_input, visit_shape, CircuitError, CircuitVisitor, InputVisitor,
+        Inputs, CIRCUITS,
+    };
+    use crate::{
+        arbitrary,
+        diagnostics::{self, ColumnRef, FailureKind, Report},
+        formula::{self, ColumnNames},
+        input::CircuitInput,
+        inspect::{ColumnKind, CsInfo},
+        mutation::{self, Mutation},
+        sizing,
+    };
+
+    // runs a copy of `visitor` on every registered circuit, with each input set
+    // to `value`
+    pub(crate) fn each_with_constant_inputs<V: CircuitVisitor + Copy>(
+        value: Fp,
+        visitor: V,
+    ) -> Vec<(&'static str, V::Output)> {
+        CIRCUITS
+            .iter()
+            .map(|info| {
+                let inputs: Inputs = info
+                    .inputs
+                    .iter()
+                    .map(|name| (name.to_string(), value))
+                    .collect();
+                (info.name, visit(info.name, &inputs, visitor).unwrap())
+            })
+            .collect()
+    }
+
+    struct Case {
+        name: &'static str,
+        // witness values, in the order of the circuit's inputs
+        inputs: &'static [u64],
+        // column names for `formula::render` and the gates it must print
+        advice: &'static [&'static str],
+        selectors: &'static [&'static str],
+        gates: &'static str,
+        // inputs and a change to the public values they imply that break the
+        // circuit, and what diagnostics must say about it
+        broken: &'static [u64],
+        tamper: fn(&mut Vec<Vec<Fp>>),
+        diagnose: fn(&Report),
+    }
+
+    const CASES: &[Case] = &[
+        Case {
+            name: "add",
+            inputs: &[5, 7],
+            advice: &["a", "b", "res"],
+            selectors: &["s"],
+            gates: "gate 0 'addition gate'\n  s * (a[cur] + b[cur] - res[cur])  (degree 2)\n",
+            // claims 13 instead of 12
+            broken: &[5, 7],
+            tamper: |instances| instances[0][2] = Fp::from(13),
+            diagnose: |report| {
+                let instance = report.failures.iter().find(|f| f.region.is_none()).unwrap();
+                assert_eq!(instance.kind, FailureKind::Permutation);
+                assert_eq!(instance.row, Some(2));
+            },
+        },
+        Case {
+            name: "add-columns",
+            inputs: &[5, 7],
+            advice: &["a", "b", "res"],
+            selectors: &["s"],
+            gates: "gate 0 'add'\n  s * (a[cur] + b[cur] - res[cur])  (degree 2)\n",
+            // the third instance column claims 13 instead of 12
+            broken: &[5, 7],
+            tamper: |instances| instances[2][0] = Fp::from(13),
+            diagnose: |report| {
+                assert!(report
+                    .failures
+                    .iter()
+                    .all(|f| f.kind == FailureKind::Permutation));
+                let columns: Vec<_> = report.failures.iter().map(|f| f.column).collect();
+                assert!(columns.contains(&Some(ColumnRef {
+                    kind: ColumnKind::Instance,
+                    index: 2
+                })));
+            },
+        },
+        Case {
+            name: "equality",
+            inputs: &[3, 3],
+            advice: &["input", "output"],
+            selectors: &["s"],
+            gates: "gate 0 'equal gate'\n  s * (input[cur] - output[cur])  (degree 2)\n",
+            // the public values match the witness, the gate has to catch 2 != 3
+            broken: &[2, 3],
+            tamper: |_| {},
+            diagnose: |report| {
+                assert_eq!(report.failures.len(), 1);
+                let failure = &report.failures[0];
+                assert_eq!(failure.kind, FailureKind::ConstraintNotSatisfied);
+                assert_eq!(failure.region.as_ref().unwrap().name, "region");
+                assert_eq!((failure.offset, failure.row), (Some(0), Some(0)));
+                assert!(report.to_string().contains("advice[1]@0 = 0x3"));
+            },
+        },
+        Case {
+            name: "equality-columns",
+            inputs: &[3, 3],
+            advice: &["input", "output"],
+            selectors: &["s"],
+            gates: "gate 0 'equal gate'\n  s * (input[cur] - output[cur])  (degree 2)\n",
+            broken: &[1, 2],
+            tamper: |_| {},
+            diagnose: |report| {
+                assert_eq!(report.failures.len(), 1);
+                let failure = &report.failures[0];
+                assert_eq!(failure.kind, FailureKind::ConstraintNotSatisfied);
+                assert_eq!(failure.gate.as_ref().unwrap().name, "equal gate");
+                assert_eq!(failure.row, Some(0));
+                let values: Vec<&str> = failure.cells.iter().map(|c| c.value.as_str()).collect();
+                assert_eq!(values, vec!["1", "0x2"]);
+            },
+        },
+        Case {
+            name: "add2",
+            inputs: &[1, 2, 3],
+            advice: &["a", "b", "c", "res"],
+            selectors: &["s_add2", "s_add"],
+            gates: "\
+gate 0 'addition gate'
+  s_add * (a[cur] + b[cur] - res[cur])  (degree 2)
+gate 1 'add 2 constraint'
+  s_add2 * (a[cur] + b[cur] + c[next] - res[next])  (degree 2)
+",
+            // claims a + b = 4; the copy-only region keeps MockProver from saying where
+            broken: &[1, 2, 3],
+            tamper: |instances| instances[0][2] = Fp::from(4),
+            diagnose: |report| {
+                assert!(!report.is_ok());
+                assert_eq!(report.failures[0].kind, FailureKind::Unlocated);
+            },
+        },
+        Case {
+            name: "fibonacci",
+            inputs: &[1, 1],
+            advice: &["a", "b", "c"],
+            selectors: &["s"],
+            gates: "gate 0 'add'\n  s * (a[cur] + b[cur] - c[cur])  (degree 2)\n",
+            // F[9] is 55
+            broken: &[1, 1],
+            tamper: |instances| instances[0][2] = Fp::from(56),
+            diagnose: |report| {
+                let last = report.failures.iter().find(|f| f.region.is_some()).unwrap();
+                assert_eq!(last.kind, FailureKind::Permutation);
+                assert_eq!(last.row, Some(7));
+            },
+        },
+    ];
+
+    struct Mock;
+
+    impl CircuitVisitor for Mock {
+        type Output = ();
+
+        fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) {
+            let k = sizing::min_k(&circuit, &instances).unwrap();
+            let prover = MockProver::run(k, &circuit, instances).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    struct RealProver;
+
+    impl CircuitVisitor for RealProver {
+        type Output = bool;
+
+        fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> bool {
+            arbitrary::proves(circuit, &instances)
+        }
+    }
+
+    // the `mutation` harness on the circuit's own input type: every public value
+    // and every input bumped by one on its own must be rejected
+    struct Mutations(Vec<Fp>);
+
+    impl InputVisitor for Mutations {
+        type Output = Vec<Mutation>;
+
+        fn visit<I: CircuitInput>(self) -> Vec<Mutation> {
+            let instances = I::from_values(&self.0).public_inputs();
+            mutation::surviving_mutations(|w| I::from_values(w).circuit(), &self.0, &instances)
+                .unwrap()
+        }
+    }
+
+    struct Diagnose(fn(&mut Vec<Vec<Fp>>));
+
+    impl CircuitVisitor for Diagnose {
+        type Output = Report;
+
+        fn visit<C: Circuit<Fp>>(self, circuit: C, mut instances: Vec<Vec<Fp>>) -> Report {
+            let k = sizing::min_k(&circuit, &instances).unwrap();
+            (self.0)(&mut instances);
+            diagnostics::check(k, &circuit, instances).unwrap()
+        }
+    }
+
+    struct Gates(ColumnNames);
+
+    impl CircuitVisitor for Gates {
+        type Output = String;
+
+        fn visit<C: Circuit<Fp>>(self, _: C, _: Vec<Vec<Fp>>) -> String {
+            formula::render(&CsInfo::of::<C>(), &self.0)
+        }
+    }
+
+    fn inputs(values: &[(&str, u64)]) -> Inputs {
+        values
+            .iter()
+            .map(|(name, v)| (name.to_string(), Fp::from(*v)))
+            .collect()
+    }
+
+    #[test]
+    fn test_every_circuit() {
+        let names: Vec<&str> = CASES.iter().map(|case| case.name).collect();
+        let registered: Vec<&str> = CIRCUITS.iter().map(|info| info.name).collect();
+        assert_eq!(names, registered);
+
+        for (case, info) in CASES.iter().zip(CIRCUITS) {
+            let named = |values: &[u64]| -> Inputs {
+                let named: Vec<_> = info
+                    .inputs
+                    .iter()
+                    .copied()
+                    .zip(values.iter().copied())
+                    .collect();
+                inputs(&named)
+            };
+            let inputs = named(case.inputs);
+
+            visit(case.name, &inputs, Mock).unwrap();
+            assert!(
+                visit(case.name, &inputs, RealProver).unwrap(),
+                "{} does not verify with the real prover",
+                case.name
+            );
+
+            let witness: Vec<Fp> = case.inputs.iter().map(|v| Fp::from(*v)).collect();
+            let survivors = visit_input(case.name, Mutations(witness)).unwrap();
+            assert!(
+                survivors.is_empty(),
+                "{} still accepts mutated {:?}",
+                case.name,
+                survivors
+            );
+
+            let names = ColumnNames::new()
+                .advice(case.advice)
+                .selectors(case.selectors);
+            let gates = visit_shape(case.name, Gates(names)).unwrap();
+            assert_eq!(gates, case.gates, "gates of {}", case.name);
+
+            let report = visit(case.name, &named(case.broken), Diagnose(case.tamper)).unwrap();
+            (case.diagnose)(&report);
+        }
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        assert_eq!(fibonacci(Fp::from(1), Fp::from(1), 9), Fp::from(55));
+    }
+
+    #[test]
+    fn test_bad_inputs() {
+        assert!(matches!(
+            visit("nope", &inputs(&[]), Mock),
+            Err(CircuitError::UnknownCircuit(_))
+        ));
+        assert!(matches!(
+            visit("add", &inputs(&[("a", 1)]), Mock),
+            Err(CircuitError::MissingInput { .. })
+        ));
+        assert!(matches!(
+            visit("add", &inputs(&[("a", 1), ("b", 2), ("c", 3)]), Mock),
+            Err(CircuitError::UnexpectedInput { .. })
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+// size and cost summary of the registered circuits, as a table or json
+use std::fmt::Write;
+
+use halo2_proofs::{
+    dev::{CircuitCost, CircuitGates},
+    pasta::{Eq, Fp},
+    plonk::{Circuit, Error},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    circuits::{self, CircuitVisitor},
+    inspect::{self, CsInfo},
+    sizing,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostReport {
+    pub circuit: String,
+    pub k: u32,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub selectors: usize,
+    pub gates: usize,
+    pub constraints: usize,
+    pub max_degree: usize,
+    pub rows: usize,
+    pub permutation_columns: usize,
+    pub lookups: usize,
+    // bytes for a proof of one circuit copy, and for each further copy
+    pub proof_size: usize,
+    pub marginal_proof_size: usize,
+    // points and scalars the verifier reads from the proof
+    pub proof_commitments: usize,
+    pub proof_evaluations: usize,
+    // size of the verifier's final multiexp: the 2^k IPA generators plus one
+    // point per commitment in the proof
+    pub verifier_msm: usize,
+}
+
+pub fn measure<C: Circuit<Fp>>(name: &str, circuit: &C) -> Result<CostReport, Error> {
+    let info = CsInfo::of::<C>();
+    let k = sizing::min_k(circuit, &[])?;
+
+    let cost = CircuitCost::<Eq, C>::measure(k as usize, circuit);
+    let (proof_commitments, proof_evaluations) = contributions(&cost);
+
+    Ok(CostReport {
+        circuit: name.to_string(),
+        k,
+        advice_columns: info.num_advice_columns,
+        fixed_columns: info.num_fixed_columns,
+        instance_columns: info.num_instance_columns,
+        selectors: info.num_selectors,
+        gates: gate_count::<C>(),
+        constraints: info.constraints.len(),
+        max_degree: info.degree,
+        rows: sizing::rows_used(circuit)?,
+        permutation_columns: info.permutation.len(),
+        lookups: info.lookups.len(),
+        proof_size: cost.proof_size(1).into(),
+        marginal_proof_size: cost.marginal_proof_size().into(),
+        proof_commitments,
+        proof_evaluations,
+        verifier_msm: (1 << k) + proof_commitments,
+    })
+}
+
+fn gate_count<C: Circuit<Fp>>() -> usize {
+    CircuitGates::collect::<Fp, C>()
+        .to_string()
+        .lines()
+        .find_map(|line| line.strip_prefix("Total gates: "))
+        .and_then(|n| n.parse().ok())
+        .expect("CircuitGates prints the gate count")
+}
+
+// totals of the per argument (commitments, evaluations) pairs in the proof size
+// breakdown, which halo2_proofs only exposes through Debug
+fn contributions<C: Circuit<Fp>>(cost: &CircuitCost<Eq, C>) -> (usize, usize) {
+    let text = format!("{:?}", cost.proof_size(1));
+    let node = inspect::parse_debug(&text).expect("proof size is valid debug output");
+
+    let mut totals = (0, 0);
+    if let inspect::Node::Struct(_, fields) = &node {
+        for (_, part) in fields
+            .iter()
+            .filter(|(_, part)| part.name() == "ProofContribution")
+        {
+            totals.0 += part.field("commitments").as_usize();
+            totals.1 += part.field("evaluations").as_usize();
+        }
+    }
+    totals
+}
+
+struct Measure<'a>(&'a str);
+
+impl CircuitVisitor for Measure<'_> {
+    type Output = Result<CostReport, Error>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, _: Vec<Vec<Fp>>) -> Self::Output {
+        measure(self.0, &circuit)
+    }
+}
+
+// reports for every circuit in the registry, costs never depend on the witness
+pub fn report_all() -> Result<Vec<CostReport>, Error> {
+    circuits::CIRCUITS
+        .iter()
+        .map(|info| circuits::visit_shape(info.name, Measure(info.name)).expect("registered"))
+        .collect()
+}
+
+const COLUMNS: &[&str] = &[
+    "circuit",
+    "k",
+    "advice",
+    "fixed",
+    "instance",
+    "selectors",
+    "gates",
+    "constraints",
+    "degree",
+    "rows",
+    "perm",
+    "lookups",
+    "proof",
+    "marginal",
+    "commits",
+    "evals",
+    "msm",
+];
+
+pub fn table(reports: &[CostReport]) -> String {
+    let rows: Vec<Vec<String>> = reports
+        .iter()
+        .map(|r| {
+            let numbers = [
+                r.k as usize,
+                r.advice_columns,
+                r.fixed_columns,
+                r.instance_columns,
+                r.selectors,
+                r.gates,
+                r.constraints,
+                r.max_degree,
+                r.rows,
+                r.permutation_columns,
+                r.lookups,
+                r.proof_size,
+                r.marginal_proof_size,
+                r.proof_commitments,
+                r.proof_evaluations,
+                r.verifier_msm,
+            ];
+            std::iter::once(r.circuit.clone())
+                .chain(numbers.iter().map(|n| n.to_string()))
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(i, title)| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .chain(Some(title.len()))
+                .max()
+                .unwrap()
+        })
+        .collect();
+
+    let mut out = String::new();
+    let titles: Vec<String> = COLUMNS.iter().map(|t| t.to_string()).collect();
+    for row in std::iter::once(&titles).chain(&rows) {
+        for (i, cell) in row.iter().enumerate() {
+            // names left aligned, numbers right aligned
+            if i == 0 {
+                write!(out, "{:<w$}", cell, w = widths[i]).unwrap();
+            } else {
+                write!(out, "  {:>w$}", cell, w = widths[i]).unwrap();
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn to_json(reports: &[CostReport]) -> String {
+    serde_json::to_string_pretty(reports).expect("reports serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::Fp;
+
+    use super::{measure, report_all, table, to_json, CostReport};
+    use crate::{circuits::CIRCUITS, example5};
+
+    #[test]
+    fn test_add2() {
+        let report = measure("add2", &example5::Add2Circuit::<Fp>::default()).unwrap();
+
+        assert_eq!(report.k, 4);
+        assert_eq!(report.advice_columns, 4);
+        assert_eq!(report.instance_columns, 1);
+        assert_eq!(report.selectors, 2);
+        assert_eq!(report.gates, 2);
+        assert_eq!(report.max_degree, 3);
+        assert_eq!(report.permutation_columns, 4);
+        assert!(report.marginal_proof_size < report.proof_size);
+        assert_eq!(report.verifier_msm, 16 + report.proof_commitments);
+    }
+
+    #[test]
+    fn test_table_and_json() {
+        let reports = report_all().unwrap();
+        assert_eq!(reports.len(), CIRCUITS.len());
+
+        let table = table(&reports);
+        assert!(table.starts_with("circuit"));
+        for info in CIRCUITS {
+            assert!(table.contains(info.name));
+        }
+
+        let parsed: Vec<CostReport> = serde_json::from_str(&to_json(&reports)).unwrap();
+        assert_eq!(parsed, reports);
+    }
+
+    // regenerate with `cargo run -- cost --json > testdata/cost.json` after a
+    // deliberate layout change
+    #[test]
+    fn test_snapshot() {
+        let expected: Vec<CostReport> =
+            serde_json::from_str(include_str!("../testdata/cost.json")).unwrap();
+        assert_eq!(report_all().unwrap(), expected);
+    }
+}
@@ -0,0 +1,521 @@
+// versioned proof file: the proof bytes together with everything a verifier
+// needs to decide whether the proof is meant for its circuit
+//
+// binary layout (integers little endian):
+//   b"H2PF" | version u16 | circuit name (u32 len + utf8) | k u32 | transcript u8
+//   | vk fingerprint [u8; 32]
+//   | instances (u32 proofs, u32 columns, u32 rows, 32-byte field elements) | proof (u32 len + bytes)
+use std::fmt;
+
+use ff::PrimeField;
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{Error, VerifyingKey},
+    poly::commitment::Params,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    field::{self, fp_to_hex, parse_fp},
+    keys::{fingerprint, Fingerprint},
+    prover::{self, Instances},
+    transcript::{TranscriptHash, TranscriptKind, TranscriptVisitor},
+};
+
+// 2 added the transcript
+pub const FORMAT_VERSION: u16 = 2;
+
+const MAGIC: &[u8; 4] = b"H2PF";
+
+#[derive(Debug)]
+pub enum EnvelopeError {
+    Decode(String),
+    Json(serde_json::Error),
+    UnsupportedVersion(u16),
+    CircuitMismatch { expected: String, found: String },
+    KMismatch { expected: u32, found: u32 },
+    VkMismatch,
+    Verify(Error),
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeError::Decode(e) => write!(f, "malformed proof envelope: {}", e),
+            EnvelopeError::Json(e) => write!(f, "malformed proof envelope json: {}", e),
+            EnvelopeError::UnsupportedVersion(v) => {
+                write!(f, "unsupported proof format version {}", v)
+            }
+            EnvelopeError::CircuitMismatch { expected, found } => write!(
+                f,
+                "proof is for circuit {:?}, expected {:?}",
+                found, expected
+            ),
+            EnvelopeError::KMismatch { expected, found } => {
+                write!(f, "proof is for k = {}, expected k = {}", found, expected)
+            }
+            EnvelopeError::VkMismatch => write!(f, "proof was made with another verifying key"),
+            EnvelopeError::Verify(e) => write!(f, "proof verification failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+impl From<serde_json::Error> for EnvelopeError {
+    fn from(e: serde_json::Error) -> Self {
+        EnvelopeError::Json(e)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofEnvelope {
+    pub version: u16,
+    pub circuit: String,
+    pub k: u32,
+    // the hash the proof's fiat-shamir challenges were drawn with
+    pub transcript: TranscriptKind,
+    pub vk_fingerprint: Fingerprint,
+    // per circuit copy, per instance column
+    pub instances: Instances,
+    pub proof: Vec<u8>,
+}
+
+impl ProofEnvelope {
+    pub fn new(
+        circuit: &str,
+        k: u32,
+        transcript: TranscriptKind,
+        vk: &VerifyingKey<EqAffine>,
+        instances: Instances,
+        proof: Vec<u8>,
+    ) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            circuit: circuit.to_string(),
+            k,
+            transcript,
+            vk_fingerprint: fingerprint(vk),
+            instances,
+            proof,
+        }
+    }
+
+    // cheap checks that run before any curve arithmetic
+    pub fn check(
+        &self,
+        circuit: &str,
+        k: u32,
+        vk: &VerifyingKey<EqAffine>,
+    ) -> Result<(), EnvelopeError> {
+        if self.version != FORMAT_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(self.version));
+        }
+        if self.circuit != circuit {
+            return Err(EnvelopeError::CircuitMismatch {
+                expected: circuit.to_string(),
+                found: self.circuit.clone(),
+            });
+        }
+        if self.k != k {
+            return Err(EnvelopeError::KMismatch {
+                expected: k,
+                found: self.k,
+            });
+        }
+        if self.vk_fingerprint != fingerprint(vk) {
+            return Err(EnvelopeError::VkMismatch);
+        }
+
+        Ok(())
+    }
+
+    pub fn verify(
+        &self,
+        circuit: &str,
+        k: u32,
+        params: &Params<EqAffine>,
+        vk: &VerifyingKey<EqAffine>,
+    ) -> Result<(), EnvelopeError> {
+        self.check(circuit, k, vk)?;
+
+        self.transcript
+            .visit(VerifyWith {
+                params,
+                vk,
+                envelope: self,
+            })
+            .map_err(EnvelopeError::Verify)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.proof.len() + 128);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&self.version.to_le_bytes());
+        put_bytes(&mut out, self.circuit.as_bytes());
+        out.extend_from_slice(&self.k.to_le_bytes());
+        out.push(self.transcript.id());
+        out.extend_from_slice(&self.vk_fingerprint);
+
+        put_len(&mut out, self.instances.len());
+        for columns in &self.instances {
+            put_len(&mut out, columns.len());
+            for column in columns {
+                put_len(&mut out, column.len());
+                for value in column {
+                    out.extend_from_slice(&value.to_repr());
+                }
+            }
+        }
+
+        put_bytes(&mut out, &self.proof);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        let mut reader = Reader(bytes);
+
+        if reader.take(4)? != MAGIC {
+            return Err(EnvelopeError::Decode("not a proof envelope".to_string()));
+        }
+        let version = u16::from_le_bytes(reader.array()?);
+        if version != FORMAT_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(version));
+        }
+
+        let circuit = String::from_utf8(reader.bytes()?.to_vec())
+            .map_err(|_| EnvelopeError::Decode("circuit name is not utf8".to_string()))?;
+        let k = u32::from_le_bytes(reader.array()?);
+        let [id] = reader.array()?;
+        let transcript = TranscriptKind::from_id(id)
+            .ok_or_else(|| EnvelopeError::Decode(format!("unknown transcript id {}", id)))?;
+        let vk_fingerprint = reader.array()?;
+
+        let mut instances = vec![];
+        for _ in 0..reader.len()? {
+            let mut columns = vec![];
+            for _ in 0..reader.len()? {
+                let mut column = vec![];
+                for _ in 0..reader.len()? {
+                    column.push(fp_from_repr(reader.array()?)?);
+                }
+                columns.push(column);
+            }
+            instances.push(columns);
+        }
+
+        let proof = reader.bytes()?.to_vec();
+        if !reader.0.is_empty() {
+            return Err(EnvelopeError::Decode("trailing bytes".to_string()));
+        }
+
+        Ok(Self {
+            version,
+            circuit,
+            k,
+            transcript,
+            vk_fingerprint,
+            instances,
+            proof,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let json = JsonEnvelope {
+            version: self.version,
+            circuit: self.circuit.clone(),
+            k: self.k,
+            transcript: self.transcript,
+            vk_fingerprint: hex::encode(self.vk_fingerprint),
+            instances: self
+                .instances
+                .iter()
+                .map(|columns| {
+                    columns
+                        .iter()
+                        .map(|column| column.iter().map(fp_to_hex).collect())
+                        .collect()
+                })
+                .collect(),
+            proof: hex::encode(&self.proof),
+        };
+
+        serde_json::to_string_pretty(&json).expect("envelope is always serializable")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, EnvelopeError> {
+        let json: JsonEnvelope = serde_json::from_str(json)?;
+        if json.version != FORMAT_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(json.version));
+        }
+
+        let vk_fingerprint = hex::decode(&json.vk_fingerprint)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| EnvelopeError::Decode("bad vk fingerprint".to_string()))?;
+
+        let instances = json
+            .instances
+            .iter()
+            .map(|columns| {
+                columns
+                    .iter()
+                    .map(|column| column.iter().map(|v| fp_from_hex(v)).collect())
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+
+        let proof = hex::decode(&json.proof)
+            .map_err(|_| EnvelopeError::Decode("proof is not hex".to_string()))?;
+
+        Ok(Self {
+            version: json.version,
+            circuit: json.circuit,
+            k: json.k,
+            transcript: json.transcript,
+            vk_fingerprint,
+            instances,
+            proof,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonEnvelope {
+    version: u16,
+    circuit: String,
+    k: u32,
+    transcript: TranscriptKind,
+    vk_fingerprint: String,
+    instances: Vec<Vec<Vec<String>>>,
+    proof: String,
+}
+
+struct VerifyWith<'a> {
+    params: &'a Params<EqAffine>,
+    vk: &'a VerifyingKey<EqAffine>,
+    envelope: &'a ProofEnvelope,
+}
+
+impl TranscriptVisitor for VerifyWith<'_> {
+    type Output = Result<(), Error>;
+
+    fn visit<T: TranscriptHash>(self) -> Self::Output {
+        prover::with_instance_slices(&self.envelope.instances, |instances| {
+            prover::verify_with::<T>(self.params, self.vk, instances, &self.envelope.proof)
+        })
+    }
+}
+
+fn fp_from_hex(value: &str) -> Result<Fp, EnvelopeError> {
+    if !value.starts_with("0x") {
+        return Err(EnvelopeError::Decode(format!(
+            "bad field element {:?}",
+            value
+        )));
+    }
+    parse_fp(value).map_err(|e| EnvelopeError::Decode(e.to_string()))
+}
+
+fn fp_from_repr(repr: [u8; 32]) -> Result<Fp, EnvelopeError> {
+    field::fp_from_repr(repr)
+        .ok_or_else(|| EnvelopeError::Decode("non-canonical field element".to_string()))
+}
+
+fn put_len(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_len(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], EnvelopeError> {
+        if self.0.len() < n {
+            return Err(EnvelopeError::Decode("unexpected end of input".to_string()));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], EnvelopeError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn len(&mut self) -> Result<usize, EnvelopeError> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], EnvelopeError> {
+        let len = self.len()?;
+        self.take(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::Value,
+        pasta::{EqAffine, Fp},
+        plonk::ProvingKey,
+        poly::commitment::Params,
+    };
+
+    use super::{EnvelopeError, ProofEnvelope, FORMAT_VERSION};
+    use crate::{
+        example3::AddCircuit,
+        example4, prover,
+        transcript::{Blake2b, Poseidon, TranscriptHash, TranscriptKind},
+    };
+
+    fn equality_envelope() -> (ProofEnvelope, Params<EqAffine>, ProvingKey<EqAffine>) {
+        equality_envelope_with::<Blake2b>()
+    }
+
+    fn equality_envelope_with<T: TranscriptHash>(
+    ) -> (ProofEnvelope, Params<EqAffine>, ProvingKey<EqAffine>) {
+        let k = 4;
+        let circuit = AddCircuit {
+            input: Value::known(Fp::from(3)),
+            output: Value::known(Fp::from(3)),
+        };
+        let (params, pk) = prover::setup(k, &circuit).unwrap();
+
+        let public_input = vec![Fp::from(3), Fp::from(3)];
+        let proof =
+            prover::prove_with::<T, _>(&params, &pk, &[circuit], &[&[&public_input[..]]]).unwrap();
+
+        let envelope = ProofEnvelope::new(
+            "equality",
+            k,
+            T::KIND,
+            pk.get_vk(),
+            vec![vec![public_input]],
+            proof,
+        );
+        (envelope, params, pk)
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let (envelope, params, pk) = equality_envelope();
+
+        let decoded = ProofEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+        assert_eq!(decoded, envelope);
+        assert!(decoded.verify("equality", 4, &params, pk.get_vk()).is_ok());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let (envelope, params, pk) = equality_envelope();
+
+        let json = envelope.to_json();
+        assert!(json.contains("\"circuit\": \"equality\""));
+
+        let decoded = ProofEnvelope::from_json(&json).unwrap();
+        assert_eq!(decoded, envelope);
+        assert!(decoded.verify("equality", 4, &params, pk.get_vk()).is_ok());
+    }
+
+    #[test]
+    fn test_poseidon_round_trip() {
+        let (envelope, params, pk) = equality_envelope_with::<Poseidon>();
+
+        let decoded = ProofEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+        assert_eq!(decoded.transcript, TranscriptKind::Poseidon);
+        assert!(decoded.verify("equality", 4, &params, pk.get_vk()).is_ok());
+
+        let json = envelope.to_json();
+        assert!(json.contains("\"transcript\": \"poseidon\""));
+        let decoded = ProofEnvelope::from_json(&json).unwrap();
+        assert_eq!(decoded, envelope);
+        assert!(decoded.verify("equality", 4, &params, pk.get_vk()).is_ok());
+
+        // the same proof read with another transcript fails
+        let mut relabeled = envelope;
+        relabeled.transcript = TranscriptKind::Blake2b;
+        assert!(matches!(
+            relabeled.verify("equality", 4, &params, pk.get_vk()),
+            Err(EnvelopeError::Verify(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_other_circuit() {
+        let (envelope, _, pk) = equality_envelope();
+
+        let res = envelope.check("add", 4, pk.get_vk());
+        assert!(matches!(res, Err(EnvelopeError::CircuitMismatch { .. })));
+
+        let (_, add_pk) = prover::setup(4, &example4::AddCircuit::<Fp>::default()).unwrap();
+        let res = envelope.check("equality", 4, add_pk.get_vk());
+        assert!(matches!(res, Err(EnvelopeError::VkMismatch)));
+    }
+
+    #[test]
+    fn test_rejects_other_k() {
+        let (envelope, _, pk) = equality_envelope();
+
+        let res = envelope.check("equality", 5, pk.get_vk());
+        assert!(matches!(
+            res,
+            Err(EnvelopeError::KMismatch {
+                expected: 5,
+                found: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        let (envelope, _, _) = equality_envelope();
+
+        let mut bytes = envelope.to_bytes();
+        bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            ProofEnvelope::from_bytes(&bytes),
+            Err(EnvelopeError::UnsupportedVersion(_))
+        ));
+
+        // magic, version, circuit name, k, then the transcript id
+        let mut bytes = envelope.to_bytes();
+        bytes[4 + 2 + 4 + "equality".len() + 4] = 9;
+        assert!(matches!(
+            ProofEnvelope::from_bytes(&bytes),
+            Err(EnvelopeError::Decode(_))
+        ));
+
+        let bytes = envelope.to_bytes();
+        assert!(matches!(
+            ProofEnvelope::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(EnvelopeError::Decode(_))
+        ));
+
+        // the modulus itself is not a canonical field element
+        let json = envelope.to_json().replace(
+            "0x0000000000000000000000000000000000000000000000000000000000000003",
+            "0x40000000000000000000000000000000224698fc094cf91b992d30ed00000001",
+        );
+        assert!(matches!(
+            ProofEnvelope::from_json(&json),
+            Err(EnvelopeError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_instance() {
+        let (mut envelope, params, pk) = equality_envelope();
+
+        envelope.instances[0][0][0] = Fp::from(4);
+        let envelope = ProofEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+        assert!(matches!(
+            envelope.verify("equality", 4, &params, pk.get_vk()),
+            Err(EnvelopeError::Verify(_))
+        ));
+    }
+}
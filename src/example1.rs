@@ -1,6 +1,7 @@
 use std::marker::PhantomData;
 
-// not working add circuit 
+// add circuit that exposes a, b and res either in one instance column or in one
+// instance column each
 use halo2_proofs::{
     arithmetic::FieldExt,
     circuit::{floor_planner::V1, *},
@@ -8,42 +9,68 @@ use halo2_proofs::{
     poly::Rotation,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstanceLayout {
+    // a, b and res on consecutive rows of a single column, from the offset on
+    SingleColumn,
+    // a column each, all on the row of the offset
+    ColumnPerValue,
+}
+
+impl InstanceLayout {
+    fn num_columns(&self) -> usize {
+        match self {
+            InstanceLayout::SingleColumn => 1,
+            InstanceLayout::ColumnPerValue => 3,
+        }
+    }
+
+    // the instance columns exposing [a, b, res] at offset 0
+    pub fn instances<F: Copy>(&self, values: [F; 3]) -> Vec<Vec<F>> {
+        match self {
+            InstanceLayout::SingleColumn => vec![values.to_vec()],
+            InstanceLayout::ColumnPerValue => values.iter().map(|v| vec![*v]).collect(),
+        }
+    }
+}
+
 #[derive(Clone)]
-struct AddConfig {
+pub struct AddConfig {
     advises: [Column<Advice>; 3],
-    instances: [Column<Instance>; 2],
+    instances: Vec<Column<Instance>>,
+    layout: InstanceLayout,
     selector: Selector,
 }
 
-struct AddChip<F: FieldExt> {
+pub struct AddChip<F: FieldExt> {
     config: AddConfig,
     _marker: PhantomData<F>,
 }
 
 impl<F: FieldExt> AddChip<F> {
-    fn construct(config: AddConfig) -> Self {
+    pub fn construct(config: AddConfig) -> Self {
         Self {
             config,
             _marker: PhantomData,
         }
     }
 
-    fn configure(meta: &mut ConstraintSystem<F>) -> AddConfig {
+    pub fn configure(meta: &mut ConstraintSystem<F>, layout: InstanceLayout) -> AddConfig {
         let advise_a = meta.advice_column();
         let advise_b = meta.advice_column();
         let advise_res = meta.advice_column();
-        let input_a = meta.instance_column();
-        let input_b = meta.instance_column();
-        let output_res = meta.instance_column();
+        let instances: Vec<_> = (0..layout.num_columns())
+            .map(|_| meta.instance_column())
+            .collect();
 
         let selector = meta.selector();
 
         meta.enable_equality(advise_a);
         meta.enable_equality(advise_b);
         meta.enable_equality(advise_res);
-        meta.enable_equality(input_a);
-        meta.enable_equality(input_b);
-        meta.enable_equality(output_res);
+        for instance in &instances {
+            meta.enable_equality(*instance);
+        }
 
         meta.create_gate("add", |meta| {
             let s = meta.query_selector(selector);
@@ -55,11 +82,13 @@ impl<F: FieldExt> AddChip<F> {
 
         AddConfig {
             advises: [advise_a, advise_b, advise_res],
-            instances: [input_a, input_b],
+            instances,
+            layout,
             selector,
         }
     }
 
+    #[allow(clippy::type_complexity)]
     pub fn assign(
         &self,
         mut layouter: impl Layouter<F>,
@@ -86,18 +115,40 @@ impl<F: FieldExt> AddChip<F> {
         &self,
         mut layouter: impl Layouter<F>,
         cells: [&AssignedCell<F, F>; 3],
-        row: usize,
+        offset: usize,
     ) -> Result<(), Error> {
-        layouter.constrain_instance(cells[0].cell(), self.config.instances[0], row)?;
-        layouter.constrain_instance(cells[1].cell(), self.config.instances[1], row)?;
-        layouter.constrain_instance(cells[2].cell(), self.config.instances[2], row)?;
+        for (i, cell) in cells.into_iter().enumerate() {
+            let (column, row) = match self.config.layout {
+                InstanceLayout::SingleColumn => (self.config.instances[0], offset + i),
+                InstanceLayout::ColumnPerValue => (self.config.instances[i], offset),
+            };
+            layouter.constrain_instance(cell.cell(), column, row)?;
+        }
 
         Ok(())
     }
 }
 
+fn synthesize_add<F: FieldExt>(
+    config: AddConfig,
+    mut layouter: impl Layouter<F>,
+    a: Value<F>,
+    b: Value<F>,
+) -> Result<(), Error> {
+    let cs = AddChip::construct(config);
+
+    let (a_cell, b_cell, res_cell) = cs.assign(layouter.namespace(|| "add region"), a, b)?;
+    cs.expose_public(
+        layouter.namespace(|| "private inputs"),
+        [&a_cell, &b_cell, &res_cell],
+        0,
+    )?;
+    Ok(())
+}
+
+// one instance column per public value
 #[derive(Default)]
-struct AddCircuit<F> {
+pub struct AddCircuit<F> {
     pub a: Value<F>,
     pub b: Value<F>,
 }
@@ -112,53 +163,103 @@ impl<F: FieldExt> Circuit<F> for AddCircuit<F> {
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        AddChip::configure(meta)
+        AddChip::configure(meta, InstanceLayout::ColumnPerValue)
     }
 
-    fn synthesize(
-        &self,
-        config: Self::Config,
-        mut layouter: impl Layouter<F>,
-    ) -> Result<(), Error> {
-        let cs = AddChip::construct(config);
-
-        let (a_cell, b_cell, res_cell) =
-            cs.assign(layouter.namespace(|| "add region"), self.a, self.b)?;
-        cs.expose_public(
-            layouter.namespace(|| "private inputs"),
-            [&a_cell, &b_cell, &res_cell],
-            0,
-        )?;
-        Ok(())
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        synthesize_add(config, layouter, self.a, self.b)
+    }
+}
+
+// the same chip with a, b and res in rows 0 to 2 of one instance column
+#[derive(Default)]
+pub struct SingleColumnAddCircuit<F> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for SingleColumnAddCircuit<F> {
+    type Config = AddConfig;
+
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        AddChip::configure(meta, InstanceLayout::SingleColumn)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        synthesize_add(config, layouter, self.a, self.b)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+    use proptest::proptest;
 
-    use super::AddCircuit;
+    use super::{AddCircuit, InstanceLayout, SingleColumnAddCircuit};
+    use crate::{arbitrary, inspect::CsInfo};
 
+    proptest! {
+        #![proptest_config(arbitrary::config())]
+
+        #[test]
+        fn test_sound(a in arbitrary::fp(), b in arbitrary::fp(), delta in arbitrary::nonzero()) {
+            let circuit = AddCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+            let public_input = InstanceLayout::ColumnPerValue.instances([a, b, a + b]);
+            arbitrary::assert_sound(&circuit, public_input, delta);
+        }
+
+        #[test]
+        fn test_single_column_sound(
+            a in arbitrary::fp(),
+            b in arbitrary::fp(),
+            delta in arbitrary::nonzero(),
+        ) {
+            let circuit = SingleColumnAddCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+            let public_input = InstanceLayout::SingleColumn.instances([a, b, a + b]);
+            arbitrary::assert_sound(&circuit, public_input, delta);
+        }
+    }
 
     #[test]
-    fn test_circuit() {
-        let k = 4;
-        let a = Fp::from(5);
-        let b = Fp::from(7);
-        let res = Fp::from(12);
+    fn test_layouts() {
+        let columns = CsInfo::of::<AddCircuit<Fp>>();
+        let single = CsInfo::of::<SingleColumnAddCircuit<Fp>>();
+        assert_eq!(columns.num_instance_columns, 3);
+        assert_eq!(single.num_instance_columns, 1);
+        // the layout only moves the public values, the gate stays the same
+        assert_eq!(columns.constraints, single.constraints);
+
+        let (a, b) = (Fp::from(5), Fp::from(7));
+        assert_eq!(
+            InstanceLayout::SingleColumn.instances([a, b, a + b]),
+            vec![vec![a, b, a + b]]
+        );
+        assert_eq!(
+            InstanceLayout::ColumnPerValue.instances([a, b, a + b]),
+            vec![vec![a], vec![b], vec![a + b]]
+        );
+    }
 
-        let circuit = AddCircuit {
+    #[test]
+    fn test_single_column_real_prover() {
+        let (a, b) = (Fp::from(5), Fp::from(7));
+        let circuit = SingleColumnAddCircuit {
             a: Value::known(a),
             b: Value::known(b),
         };
-
-        let public_input = vec![a, b, res];
-        let prover_res = MockProver::run(k, &circuit, vec![public_input.clone()]);
-
-        if prover_res.is_err() {
-            println!("{:?}", prover_res.err());
-        }
-
-        // prover.assert_satisfied();
+        let public_input = InstanceLayout::SingleColumn.instances([a, b, a + b]);
+        assert!(arbitrary::proves(circuit, &public_input));
     }
 }
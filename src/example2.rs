@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-// not working simple equality circuit
+// simple equality circuit with one instance column per public value
 use halo2_proofs::{
     arithmetic::FieldExt,
     circuit::{SimpleFloorPlanner, Value},
@@ -9,13 +9,15 @@ use halo2_proofs::{
 };
 
 #[derive(Clone)]
-struct AddConfig {
+pub struct AddConfig {
     pub advice: [Column<Advice>; 2],
     pub selector: Selector,
     pub instance: [Column<Instance>; 2],
 }
 
-struct AddChip<F: FieldExt> {
+pub struct AddChip<F: FieldExt> {
+    // synthesize lays the region out itself and never builds the chip
+    #[allow(dead_code)]
     config: AddConfig,
     _marker: PhantomData<F>,
 }
@@ -57,7 +59,7 @@ impl<F: FieldExt> AddChip<F> {
 }
 
 #[derive(Default)]
-struct AddCircuit<F> {
+pub struct AddCircuit<F> {
     pub input: Value<F>,
     pub output: Value<F>,
 }
@@ -104,28 +106,33 @@ impl<F: FieldExt> Circuit<F> for AddCircuit<F> {
 
 #[cfg(test)]
 mod tests {
-    use halo2_proofs::{
-        circuit::{self, Value},
-        dev::MockProver,
-        pasta::Fp,
-    };
+    use halo2_proofs::circuit::Value;
+    use proptest::{prop_assert, proptest};
 
     use super::AddCircuit;
+    use crate::arbitrary;
+
+    proptest! {
+        #![proptest_config(arbitrary::config())]
+
+        #[test]
+        fn test1(x in arbitrary::fp()) {
+            let circuit = AddCircuit {
+                input: Value::known(x),
+                output: Value::known(x),
+            };
+            prop_assert!(arbitrary::satisfied(&circuit, vec![vec![x], vec![x]]));
+        }
 
-    #[test]
-    fn test1() {
-        let k = 4;
-        let input = Fp::from(5);
-        let output = Fp::from(5);
-
-        let circuit = AddCircuit {
-            input: Value::known(input),
-            output: Value::known(output),
-        };
-
-        let public_input = vec![input, output];
-
-        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
-        prover.assert_satisfied();
+        // the instances match the witness, the gate has to catch input != output
+        #[test]
+        fn test_perturbed(x in arbitrary::fp(), delta in arbitrary::nonzero()) {
+            let (input, output) = (x, x + delta);
+            let circuit = AddCircuit {
+                input: Value::known(input),
+                output: Value::known(output),
+            };
+            prop_assert!(!arbitrary::satisfied(&circuit, vec![vec![input], vec![output]]));
+        }
     }
 }
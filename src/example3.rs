@@ -9,13 +9,13 @@ use halo2_proofs::{
 };
 
 #[derive(Clone)]
-struct AddConfig {
+pub struct AddConfig {
     advices: [Column<Advice>; 2],
     selector: Selector,
     instance: Column<Instance>, // instances: [Column<Instance>; 2],
 }
 
-struct AddChip<F> {
+pub struct AddChip<F> {
     config: AddConfig,
     _marker: PhantomData<F>,
 }
@@ -55,6 +55,7 @@ impl<F: FieldExt> AddChip<F> {
         }
     }
 
+    #[allow(clippy::type_complexity)]
     fn assign(
         &self,
         mut layouter: impl Layouter<F>,
@@ -92,27 +93,17 @@ impl<F: FieldExt> AddChip<F> {
         input_cell: AssignedCell<F, F>,
         output_cell: AssignedCell<F, F>,
     ) -> Result<(), Error> {
-        let input_res = layouter
-            .constrain_instance(input_cell.cell(), self.config.instance, 0)
-            .or_else(|e| Err(e));
-        if input_res.is_err() {
-            return input_res;
-        }
+        layouter.constrain_instance(input_cell.cell(), self.config.instance, 0)?;
+        layouter.constrain_instance(output_cell.cell(), self.config.instance, 1)?;
 
-        let output_res = layouter
-            .constrain_instance(output_cell.cell(), self.config.instance, 1)
-            .or_else(|e| Err(e));
-        if output_res.is_err() {
-            return output_res;
-        }
         Ok(())
     }
 }
 
 #[derive(Default, Clone)]
-struct AddCircuit<F> {
-    input: Value<F>,
-    output: Value<F>,
+pub struct AddCircuit<F> {
+    pub input: Value<F>,
+    pub output: Value<F>,
 }
 
 impl<F: FieldExt> Circuit<F> for AddCircuit<F> {
@@ -149,32 +140,56 @@ impl<F: FieldExt> Circuit<F> for AddCircuit<F> {
 #[cfg(test)]
 mod tests {
 
-    use halo2_proofs::{
-        circuit::Value,
-        dev::MockProver,
-        pasta::{EqAffine, Fp},
-        plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, SingleVerifier},
-        poly::commitment::Params,
-        transcript::{Blake2bRead, Blake2bWrite, Challenge255},
-    };
-    use rand_core::OsRng;
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+    use proptest::{prop_assert, proptest};
 
     use super::AddCircuit;
-    #[test]
-    fn test1() {
-        let k = 4;
-        let input = Fp::from(1);
-        let output = Fp::from(1);
+    use crate::{
+        arbitrary,
+        layout::{Format, Layout},
+        prover,
+        transcript::Poseidon,
+    };
 
-        let circuit = AddCircuit {
-            input: Value::known(input),
-            output: Value::known(output),
-        };
+    proptest! {
+        #![proptest_config(arbitrary::config())]
 
-        let public_input = vec![input, output];
+        #[test]
+        fn test1(x in arbitrary::fp()) {
+            let circuit = AddCircuit {
+                input: Value::known(x),
+                output: Value::known(x),
+            };
+            prop_assert!(arbitrary::satisfied(&circuit, vec![vec![x, x]]));
+        }
+
+        // the instances match the witness, the gate has to catch input != output
+        #[test]
+        fn test_perturbed(x in arbitrary::fp(), delta in arbitrary::nonzero()) {
+            let (input, output) = (x, x + delta);
+            let circuit = AddCircuit {
+                input: Value::known(input),
+                output: Value::known(output),
+            };
+            prop_assert!(!arbitrary::satisfied(&circuit, vec![vec![input, output]]));
+        }
+    }
+
+    // `cargo run -- layout --circuit <name> --format html --out <file>` writes the
+    // page for viewing
+    #[test]
+    fn test_layout_html() {
+        let circuit = AddCircuit::<Fp> {
+            input: Value::unknown(),
+            output: Value::unknown(),
+        };
+        let layout = Layout::of(&circuit, &[]).unwrap();
 
-        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
-        prover.assert_satisfied();
+        let html = layout.render(Format::Html, "Example3 Layout");
+        assert!(html.contains("<h1>Example3 Layout</h1>"));
+        for region in &layout.recording.regions {
+            assert!(html.contains(&region.name));
+        }
     }
 
     #[cfg(feature = "dev-graph")]
@@ -197,48 +212,49 @@ mod tests {
 
     #[test]
     fn test_real_prover() {
-        let k = 4;
-        let params: Params<EqAffine> = Params::new(k);
-
-        let empty_circuit: AddCircuit<Fp> = AddCircuit {
-            input: Value::unknown(),
-            output: Value::unknown(),
-        };
         let input = Fp::from(1);
         let output = Fp::from(1);
 
-        let public_input = vec![input, output];
+        let public_input = [input, output];
 
         let circuit = AddCircuit {
             input: Value::known(input),
             output: Value::known(output),
         };
 
-        let vk = keygen_vk(&params, &empty_circuit).expect("keygen_vk should not fail");
-        let pk = keygen_pk(&params, vk, &empty_circuit).expect("keygen_pk should not fail");
+        let (_, params, pk) =
+            prover::setup_min_k(&circuit, &[public_input.to_vec()]).expect("setup should not fail");
 
-        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-        // Create a proof
-        create_proof(
+        // Create a proof for two copies of the circuit
+        let instances: &[&[Fp]] = &[&public_input[..]];
+        let proof = prover::prove(
             &params,
             &pk,
-            &[circuit.clone(), circuit.clone()],
-            &[&[&public_input[..]], &[&public_input[..]]],
-            OsRng,
-            &mut transcript,
+            &[circuit.clone(), circuit],
+            &[instances, instances],
         )
         .expect("proof generation should not fail");
-        let proof: Vec<u8> = transcript.finalize();
 
-        let strategy = SingleVerifier::new(&params);
-        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
-        assert!(verify_proof(
-            &params,
-            pk.get_vk(),
-            strategy,
-            &[&[&public_input[..]], &[&public_input[..]]],
-            &mut transcript,
-        )
-        .is_ok());
+        assert!(prover::verify(&params, pk.get_vk(), &[instances, instances], &proof).is_ok());
+    }
+
+    #[test]
+    fn test_poseidon_transcript() {
+        let input = Fp::from(1);
+        let public_input = [input, input];
+
+        let circuit = AddCircuit {
+            input: Value::known(input),
+            output: Value::known(input),
+        };
+        let (_, params, pk) = prover::setup_min_k(&circuit, &[public_input.to_vec()]).unwrap();
+
+        let instances: &[&[Fp]] = &[&public_input[..]];
+        let proof =
+            prover::prove_with::<Poseidon, _>(&params, &pk, &[circuit], &[instances]).unwrap();
+        assert!(
+            prover::verify_with::<Poseidon>(&params, pk.get_vk(), &[instances], &proof).is_ok()
+        );
+        assert!(prover::verify(&params, pk.get_vk(), &[instances], &proof).is_err());
     }
 }
@@ -9,13 +9,13 @@ use halo2_proofs::{
 
 // a + b = c
 #[derive(Clone)]
-struct AddConfig {
+pub struct AddConfig {
     advices: [Column<Advice>; 3],
     selector: Selector,
     instance: Column<Instance>,
 }
 
-struct AddChip<F> {
+pub struct AddChip<F> {
     config: AddConfig,
     _marker: PhantomData<F>,
 }
@@ -58,6 +58,7 @@ impl<F: FieldExt> AddChip<F> {
         }
     }
 
+    #[allow(clippy::type_complexity)]
     fn assign(
         &self,
         mut layouter: impl Layouter<F>,
@@ -100,9 +101,9 @@ impl<F: FieldExt> AddChip<F> {
 }
 
 #[derive(Default)]
-struct AddCircuit<F> {
-    a: Value<F>,
-    b: Value<F>,
+pub struct AddCircuit<F> {
+    pub a: Value<F>,
+    pub b: Value<F>,
 }
 
 impl<F: FieldExt> Circuit<F> for AddCircuit<F> {
@@ -135,24 +136,22 @@ impl<F: FieldExt> Circuit<F> for AddCircuit<F> {
 
 #[cfg(test)]
 mod tests {
-    use halo2_proofs::{circuit::Value, pasta::Fp, dev::MockProver};
+    use halo2_proofs::circuit::Value;
+    use proptest::proptest;
 
     use super::AddCircuit;
-
-    #[test]
-    fn test() {
-        let k = 4;
-        let a = Fp::from(5);
-        let b = Fp::from(1);
-        let res = a + b;
-
-        let circuit = AddCircuit {
-            a: Value::known(a),
-            b: Value::known(b),
-        };
-        let public_input = vec![a, b, res];
-
-        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
-        prover.assert_satisfied();
+    use crate::arbitrary;
+
+    proptest! {
+        #![proptest_config(arbitrary::config())]
+
+        #[test]
+        fn test_sound(a in arbitrary::fp(), b in arbitrary::fp(), delta in arbitrary::nonzero()) {
+            let circuit = AddCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+            arbitrary::assert_sound(&circuit, vec![vec![a, b, a + b]], delta);
+        }
     }
 }
@@ -59,6 +59,7 @@ impl<F: FieldExt> AddChip<F> {
         }
     }
 
+    #[allow(clippy::type_complexity)]
     pub fn assign(
         &self,
         mut layouter: impl Layouter<F>,
@@ -101,9 +102,9 @@ impl<F: FieldExt> AddChip<F> {
 }
 
 #[derive(Default)]
-struct AddCircuit<F> {
-    a: Value<F>,
-    b: Value<F>,
+pub struct AddCircuit<F> {
+    pub a: Value<F>,
+    pub b: Value<F>,
 }
 
 impl<F: FieldExt> Circuit<F> for AddCircuit<F> {
@@ -141,24 +142,88 @@ impl<F: FieldExt> Circuit<F> for AddCircuit<F> {
 
 #[cfg(test)]
 mod tests {
-    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+    use proptest::proptest;
 
     use super::AddCircuit;
+    use crate::{
+        arbitrary,
+        diagnostics::{self, FailureKind},
+        formula::{self, ColumnNames},
+        inspect::CsInfo,
+        mutation, sizing,
+    };
+
+    proptest! {
+        #![proptest_config(arbitrary::config())]
+
+        #[test]
+        fn test_sound(a in arbitrary::fp(), b in arbitrary::fp(), delta in arbitrary::nonzero()) {
+            let circuit = AddCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+            arbitrary::assert_sound(&circuit, vec![vec![a, b, a + b]], delta);
+        }
+    }
 
+    // not in the circuit registry, so not covered by its table test
     #[test]
-    fn test() {
-        let k = 4;
-        let a = Fp::from(5);
-        let b = Fp::from(1);
-        let res = a + b;
+    fn test_real_prover() {
+        let (a, b) = (Fp::from(5), Fp::from(1));
+        let circuit = AddCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        assert!(arbitrary::proves(circuit, &[vec![a, b, a + b]]));
+    }
 
+    #[test]
+    fn test_broken_witness() {
+        let (a, b) = (Fp::from(5), Fp::from(7));
         let circuit = AddCircuit {
             a: Value::known(a),
             b: Value::known(b),
         };
-        let public_input = vec![a, b, res];
 
-        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
-        prover.assert_satisfied();
+        // claims a = 6
+        let public_input = vec![vec![Fp::from(6), b, a + b]];
+        let k = sizing::min_k(&circuit, &public_input).unwrap();
+        let report = diagnostics::check(k, &circuit, public_input).unwrap();
+
+        let rows: Vec<Option<usize>> = report
+            .failures
+            .iter()
+            .filter(|f| f.region.is_none())
+            .map(|f| f.row)
+            .collect();
+        assert_eq!(rows, vec![Some(0)]);
+        assert!(report
+            .failures
+            .iter()
+            .all(|f| f.kind == FailureKind::Permutation));
+    }
+
+    #[test]
+    fn test_mutations() {
+        let (a, b) = (Fp::from(5), Fp::from(7));
+        mutation::assert_rejects_mutations(
+            |w| AddCircuit {
+                a: Value::known(w[0]),
+                b: Value::known(w[1]),
+            },
+            &[a, b],
+            &[vec![a, b, a + b]],
+        );
+    }
+
+    #[test]
+    fn test_gates() {
+        let names = ColumnNames::new()
+            .advice(["a", "b", "res"])
+            .selectors(["s"]);
+        let gates = formula::render(&CsInfo::of::<AddCircuit<Fp>>(), &names);
+        let expected = "gate 0 'addition gate'\n  s * (a[cur] + b[cur] - res[cur])  (degree 2)\n";
+        assert_eq!(gates, expected);
     }
 }
@@ -14,14 +14,14 @@ use crate::example4_gadget::{AddChip, AddConfig};
 // add a+b+c
 
 #[derive(Clone)]
-struct Add2Config {
-    advices: [Column<Advice>; 4],
-    selector: Selector,
-    instance: Column<Instance>,
-    add_config: AddConfig,
+pub struct Add2Config {
+    pub advices: [Column<Advice>; 4],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+    pub add_config: AddConfig,
 }
 
-struct Add2Chip<F> {
+pub struct Add2Chip<F> {
     config: Add2Config,
     _marker: PhantomData<F>,
 }
@@ -77,7 +77,7 @@ impl<F: FieldExt> Add2Chip<F> {
         let add_cs_2 = AddChip::<F>::construct(self.config.add_config.clone());
         let res_1_value = c_cell.value().copied();
 
-        let (res1_cell, c_cell, res_cell) = add_cs_2
+        let (res1_cell, _c_cell, res_cell) = add_cs_2
             .assign(layouter.namespace(|| "result assign"), res_1_value, c, 1)
             .unwrap();
 
@@ -95,10 +95,10 @@ impl<F: FieldExt> Add2Chip<F> {
 }
 
 #[derive(Default)]
-struct Add2Circuit<F> {
-    a: Value<F>,
-    b: Value<F>,
-    c: Value<F>,
+pub struct Add2Circuit<F> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+    pub c: Value<F>,
 }
 
 impl<F: FieldExt> Circuit<F> for Add2Circuit<F> {
@@ -128,27 +128,30 @@ impl<F: FieldExt> Circuit<F> for Add2Circuit<F> {
 
 #[cfg(test)]
 mod tests {
-    use halo2_proofs::{pasta::Fp, circuit::Value, dev::MockProver};
+    use halo2_proofs::circuit::Value;
+    use proptest::proptest;
 
     use super::Add2Circuit;
-
-    #[test]
-    fn test() {
-        let k = 4;
-        let a = Fp::from(5);
-        let b = Fp::from(7);
-        let c = Fp::from(12);
-        let res = a + b + c;
-
-        let circuit = Add2Circuit {
-            a: Value::known(a),
-            b: Value::known(b),
-            c: Value::known(c),
-        };
-
-        let pub_instances = vec![a, b, c, res];
-
-        let prover = MockProver::run(k, &circuit, vec![pub_instances.clone()]).unwrap();
-        prover.assert_satisfied();
+    use crate::arbitrary;
+
+    proptest! {
+        #![proptest_config(arbitrary::config())]
+
+        #[test]
+        fn test_sound(
+            a in arbitrary::fp(),
+            b in arbitrary::fp(),
+            c in arbitrary::fp(),
+            delta in arbitrary::nonzero(),
+        ) {
+            let circuit = Add2Circuit {
+                a: Value::known(a),
+                b: Value::known(b),
+                c: Value::known(c),
+            };
+            // the intermediate sum a + b is public too
+            let public_input = vec![vec![a, b, a + b, a + b + c]];
+            arbitrary::assert_sound(&circuit, public_input, delta);
+        }
     }
-}
\ No newline at end of file
+}
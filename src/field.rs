@@ -0,0 +1,200 @@
+// text encoding of field elements: decimal, or 0x-prefixed big endian hex
+use std::fmt;
+
+use ff::PrimeField;
+use halo2_proofs::pasta::Fp;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldError {
+    Empty,
+    InvalidDigit(String),
+    // the integer does not fit in 256 bits or is not below the modulus
+    NonCanonical(String),
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::Empty => write!(f, "empty field element"),
+            FieldError::InvalidDigit(s) => write!(f, "invalid field element {:?}", s),
+            FieldError::NonCanonical(s) => {
+                write!(f, "field element {:?} is not below the modulus", s)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+pub fn fp_to_hex(value: &Fp) -> String {
+    let mut repr = value.to_repr();
+    repr.reverse();
+    format!("0x{}", hex::encode(repr))
+}
+
+pub fn fp_from_repr(repr: [u8; 32]) -> Option<Fp> {
+    Option::from(Fp::from_repr(repr))
+}
+
+pub fn parse_fp(s: &str) -> Result<Fp, FieldError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(FieldError::Empty);
+    }
+
+    // little endian 256 bit integer
+    let mut repr = [0u8; 32];
+    let (digits, radix) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(digits) => (digits, 16),
+        None => (s, 10),
+    };
+    // underscores only between digits, so "_" and "0x_" are not zero
+    if digits.is_empty() || digits.starts_with('_') || digits.ends_with('_') {
+        return Err(FieldError::InvalidDigit(s.to_string()));
+    }
+
+    for c in digits.chars().filter(|c| *c != '_') {
+        let digit = c
+            .to_digit(radix)
+            .ok_or_else(|| FieldError::InvalidDigit(s.to_string()))?;
+
+        let mut carry = digit;
+        for byte in repr.iter_mut() {
+            let v = (*byte as u32) * radix + carry;
+            *byte = v as u8;
+            carry = v >> 8;
+        }
+        if carry != 0 {
+            return Err(FieldError::NonCanonical(s.to_string()));
+        }
+    }
+
+    fp_from_repr(repr).ok_or_else(|| FieldError::NonCanonical(s.to_string()))
+}
+
+// json inputs may give small values as plain numbers
+pub fn fp_from_json(value: &serde_json::Value) -> Result<Fp, FieldError> {
+    match value {
+        serde_json::Value::String(s) => parse_fp(s),
+        serde_json::Value::Number(n) => parse_fp(&n.to_string()),
+        other => Err(FieldError::InvalidDigit(other.to_string())),
+    }
+}
+
+// a field element in an input file: a decimal or hex string, or a small integer.
+// Serialized as hex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldValue(pub Fp);
+
+impl Serialize for FieldValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&fp_to_hex(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl de::Visitor<'_> for Visitor {
+            type Value = FieldValue;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a field element as a decimal or 0x hex string")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<FieldValue, E> {
+                parse_fp(s).map(FieldValue).map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, n: u64) -> Result<FieldValue, E> {
+                Ok(FieldValue(Fp::from(n)))
+            }
+
+            fn visit_i64<E: de::Error>(self, n: i64) -> Result<FieldValue, E> {
+                u64::try_from(n)
+                    .map(|n| FieldValue(Fp::from(n)))
+                    .map_err(|_| E::custom(FieldError::InvalidDigit(n.to_string())))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::Fp;
+
+    use serde_json::json;
+
+    use super::{fp_from_json, fp_to_hex, parse_fp, FieldError, FieldValue};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_fp("12").unwrap(), Fp::from(12));
+        assert_eq!(parse_fp("0x0c").unwrap(), Fp::from(12));
+        assert_eq!(parse_fp("1_000").unwrap(), Fp::from(1000));
+        assert_eq!(parse_fp(&fp_to_hex(&-Fp::one())).unwrap(), -Fp::one());
+
+        // modulus - 1
+        let max = "28948022309329048855892746252171976963363056481941560715954676764349967630336";
+        assert_eq!(parse_fp(max).unwrap(), -Fp::one());
+        assert_eq!(fp_to_hex(&Fp::from(12)), format!("0x{}0c", "0".repeat(62)));
+    }
+
+    #[test]
+    fn test_reject() {
+        assert_eq!(parse_fp(""), Err(FieldError::Empty));
+        assert!(matches!(parse_fp("0x"), Err(FieldError::InvalidDigit(_))));
+        assert!(matches!(parse_fp("12a"), Err(FieldError::InvalidDigit(_))));
+        assert!(matches!(parse_fp("-1"), Err(FieldError::InvalidDigit(_))));
+        for s in ["_", "__", "0x_", "_1", "1_", "0x_ff", "0xff_"] {
+            assert!(
+                matches!(parse_fp(s), Err(FieldError::InvalidDigit(_))),
+                "{}",
+                s
+            );
+        }
+
+        // the modulus itself and anything above 2^256
+        let modulus =
+            "28948022309329048855892746252171976963363056481941560715954676764349967630337";
+        assert!(matches!(
+            parse_fp(modulus),
+            Err(FieldError::NonCanonical(_))
+        ));
+        assert!(matches!(
+            parse_fp(&format!("0x1{}", "0".repeat(64))),
+            Err(FieldError::NonCanonical(_))
+        ));
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(fp_from_json(&json!(12)).unwrap(), Fp::from(12));
+        assert_eq!(fp_from_json(&json!("0x0c")).unwrap(), Fp::from(12));
+        assert!(fp_from_json(&json!(-1)).is_err());
+        assert!(fp_from_json(&json!(null)).is_err());
+    }
+
+    #[test]
+    fn test_field_value() {
+        let value: FieldValue = serde_json::from_value(json!("0x0c")).unwrap();
+        assert_eq!(value, FieldValue(Fp::from(12)));
+        assert_eq!(
+            serde_json::to_value(value).unwrap(),
+            json!(fp_to_hex(&Fp::from(12)))
+        );
+
+        let value: FieldValue = serde_json::from_value(json!(12)).unwrap();
+        assert_eq!(value.0, Fp::from(12));
+        assert!(serde_json::from_value::<FieldValue>(json!(-1)).is_err());
+        assert!(serde_json::from_value::<FieldValue>(json!(1.5)).is_err());
+
+        let err = serde_json::from_value::<FieldValue>(json!(format!("0x{}", "f".repeat(64))))
+            .unwrap_err();
+        assert!(err.to_string().contains("not below the modulus"), "{}", err);
+    }
+}
@@ -0,0 +1,244 @@
+// gates as formulas: `s_add * (a[cur] + b[cur] - res[cur])`, with the column
+// names a chip gives its columns and the degree of every constraint
+use std::fmt::Write;
+
+use ff::PrimeField;
+use halo2_proofs::pasta::Fp;
+
+use crate::{
+    field::{fp_to_hex, parse_fp},
+    inspect::{rotation_name, ColumnKind, CsInfo, Expr},
+};
+
+// names by column index, in the order the columns were created. Columns left
+// unnamed print as the layout labels a0, f0, i0 and s0
+#[derive(Debug, Clone, Default)]
+pub struct ColumnNames {
+    advice: Vec<String>,
+    fixed: Vec<String>,
+    instance: Vec<String>,
+    selectors: Vec<String>,
+}
+
+impl ColumnNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advice<S: ToString>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.advice = names.into_iter().map(|n| n.to_string()).collect();
+        self
+    }
+
+    pub fn fixed<S: ToString>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.fixed = names.into_iter().map(|n| n.to_string()).collect();
+        self
+    }
+
+    pub fn instance<S: ToString>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.instance = names.into_iter().map(|n| n.to_string()).collect();
+        self
+    }
+
+    pub fn selectors<S: ToString>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.selectors = names.into_iter().map(|n| n.to_string()).collect();
+        self
+    }
+
+    pub fn column(&self, kind: ColumnKind, index: usize) -> String {
+        let (names, prefix) = match kind {
+            ColumnKind::Advice => (&self.advice, "a"),
+            ColumnKind::Fixed => (&self.fixed, "f"),
+            ColumnKind::Instance => (&self.instance, "i"),
+        };
+        names
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| format!("{}{}", prefix, index))
+    }
+
+    pub fn selector(&self, index: usize) -> String {
+        self.selectors
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| format!("s{}", index))
+    }
+}
+
+// binding strength, a subexpression weaker than its context gets parentheses
+const SUM: u8 = 0;
+const PRODUCT: u8 = 1;
+const ATOM: u8 = 2;
+
+pub fn formula(expr: &Expr, names: &ColumnNames) -> String {
+    let mut out = String::new();
+    write_expr(&mut out, expr, names, SUM);
+    out
+}
+
+fn write_expr(out: &mut String, expr: &Expr, names: &ColumnNames, context: u8) {
+    let strength = match expr {
+        Expr::Sum(..) => SUM,
+        Expr::Product(..) | Expr::Scaled(..) => PRODUCT,
+        _ => ATOM,
+    };
+    if strength < context {
+        out.push('(');
+    }
+
+    match expr {
+        Expr::Constant(value) => out.push_str(&constant(value)),
+        Expr::Selector(index) => out.push_str(&names.selector(*index)),
+        Expr::Query {
+            kind,
+            column,
+            rotation,
+        } => write!(
+            out,
+            "{}[{}]",
+            names.column(*kind, *column),
+            rotation_name(*rotation)
+        )
+        .unwrap(),
+        Expr::Negated(a) => {
+            out.push('-');
+            write_expr(out, a, names, ATOM);
+        }
+        Expr::Sum(a, b) => {
+            write_expr(out, a, names, SUM);
+            // halo2 writes a - b as a + (-b)
+            let (sign, b) = match b.as_ref() {
+                Expr::Negated(b) => (" - ", b.as_ref()),
+                b => (" + ", b),
+            };
+            out.push_str(sign);
+            write_expr(out, b, names, PRODUCT);
+        }
+        Expr::Product(a, b) => {
+            write_expr(out, a, names, PRODUCT);
+            out.push_str(" * ");
+            write_expr(out, b, names, PRODUCT);
+        }
+        Expr::Scaled(a, factor) => {
+            write!(out, "{} * ", constant(factor)).unwrap();
+            write_expr(out, a, names, PRODUCT);
+        }
+    }
+
+    if strength < context {
+        out.push(')');
+    }
+}
+
+// small constants and their negations in decimal, anything else in hex
+fn constant(text: &str) -> String {
+    let Ok(value) = parse_fp(text) else {
+        return text.to_string();
+    };
+    // the repr is little endian
+    let small = |value: &Fp| {
+        let repr = value.to_repr();
+        let (low, high) = repr.split_at(8);
+        let low: [u8; 8] = low.try_into().expect("8 bytes");
+        high.iter()
+            .all(|b| *b == 0)
+            .then(|| u64::from_le_bytes(low))
+    };
+    match (small(&value), small(&-value)) {
+        (Some(n), _) => n.to_string(),
+        (None, Some(n)) => format!("-{}", n),
+        _ => fp_to_hex(&value),
+    }
+}
+
+// every gate with its constraints, one per line with its degree, then the lookups
+pub fn render(info: &CsInfo, names: &ColumnNames) -> String {
+    let mut out = String::new();
+    let mut constraints = info.constraints.iter();
+
+    for (index, gate) in info.gates.iter().enumerate() {
+        writeln!(out, "gate {} '{}'", index, gate.name).unwrap();
+        for name in &gate.constraint_names {
+            let constraint = constraints.next().expect("a constraint per name");
+            out.push_str("  ");
+            if !name.is_empty() {
+                write!(out, "{}: ", name).unwrap();
+            }
+            writeln!(
+                out,
+                "{}  (degree {})",
+                formula(constraint, names),
+                constraint.degree()
+            )
+            .unwrap();
+        }
+    }
+
+    for (index, lookup) in info.lookups.iter().enumerate() {
+        let side = |exprs: &[Expr]| -> String {
+            let exprs: Vec<String> = exprs.iter().map(|e| formula(e, names)).collect();
+            exprs.join(", ")
+        };
+        writeln!(
+            out,
+            "lookup {}\n  ({}) in ({})",
+            index,
+            side(&lookup.input),
+            side(&lookup.table)
+        )
+        .unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::Fp;
+
+    use super::{constant, formula, ColumnNames};
+    use crate::{
+        field::fp_to_hex,
+        inspect::{ColumnKind, Expr},
+    };
+
+    fn query(column: usize, rotation: i32) -> Expr {
+        Expr::Query {
+            kind: ColumnKind::Advice,
+            column,
+            rotation,
+        }
+    }
+
+    #[test]
+    fn test_formula() {
+        let names = ColumnNames::new().advice(["a", "b"]);
+        let (a, b, c) = (query(0, 0), query(1, -1), query(2, 2));
+
+        // (a - b) * c[+2] + -a
+        let expr = Expr::Sum(
+            Box::new(Expr::Product(
+                Box::new(Expr::Sum(
+                    Box::new(a.clone()),
+                    Box::new(Expr::Negated(Box::new(b))),
+                )),
+                Box::new(c),
+            )),
+            Box::new(Expr::Negated(Box::new(a.clone()))),
+        );
+        assert_eq!(
+            formula(&expr, &names),
+            "(a[cur] - b[prev]) * a2[+2] - a[cur]"
+        );
+
+        let scaled = Expr::Scaled(Box::new(a), fp_to_hex(&Fp::from(3)));
+        assert_eq!(formula(&scaled, &names), "3 * a[cur]");
+    }
+
+    #[test]
+    fn test_constant() {
+        assert_eq!(constant(&fp_to_hex(&Fp::zero())), "0");
+        assert_eq!(constant(&fp_to_hex(&-Fp::from(2))), "-2");
+        let big = fp_to_hex(&Fp::from(u64::MAX).square());
+        assert_eq!(constant(&big), big);
+    }
+}
@@ -0,0 +1,169 @@
+// circuits as graphviz dot. `namespaces` is halo2's graph of the layouter
+// namespaces a circuit opens, `gates` draws its constraint system instead: each
+// gate and its constraints, the columns they query at which rotation and the
+// selectors that switch them on
+use std::collections::BTreeSet;
+
+use halo2_proofs::{dev::circuit_dot_graph, pasta::Fp, plonk::Circuit};
+use tabbycat::{
+    attributes::{label, shape, Shape},
+    AttrList, Edge, GraphBuilder, GraphType, Identity, StmtList,
+};
+
+use crate::inspect::{rotation_name, ColumnKind, CsInfo};
+
+pub fn namespaces<C: Circuit<Fp>>(circuit: &C) -> String {
+    circuit_dot_graph(circuit)
+}
+
+pub fn gates(info: &CsInfo) -> String {
+    // tabbycat borrows ids and labels, so every string is built before the graph
+    let mut nodes: Vec<(String, String)> = vec![];
+    let mut shapes: Vec<Shape> = vec![];
+    let mut edges: Vec<(String, String, String)> = vec![];
+    let mut columns = BTreeSet::new();
+    let mut selectors = BTreeSet::new();
+
+    for (index, gate) in info.gates.iter().enumerate() {
+        nodes.push((
+            format!("gate{}", index),
+            format!("gate {}: {}", index, gate.name),
+        ));
+        shapes.push(Shape::Box);
+    }
+
+    let names = info.gates.iter().flat_map(|gate| &gate.constraint_names);
+    let constraints = info
+        .constraints
+        .iter()
+        .zip(info.constraint_gates())
+        .zip(names);
+    for (index, ((constraint, gate), name)) in constraints.enumerate() {
+        let id = format!("constraint{}", index);
+        let mut text = format!("constraint {}", index);
+        if !name.is_empty() {
+            text.push_str(&format!(": {}", name));
+        }
+        text.push_str(&format!("\ndegree {}", constraint.degree()));
+        nodes.push((id.clone(), text));
+        shapes.push(Shape::Ellipse);
+        edges.push((format!("gate{}", gate), id.clone(), String::new()));
+
+        for (kind, column, rotation) in constraint.queries() {
+            columns.insert((kind, column));
+            edges.push((id.clone(), column_id(kind, column), rotation_name(rotation)));
+        }
+        for selector in constraint.selectors() {
+            selectors.insert(selector);
+            edges.push((id.clone(), format!("selector{}", selector), String::new()));
+        }
+    }
+
+    for (index, lookup) in info.lookups.iter().enumerate() {
+        let id = format!("lookup{}", index);
+        nodes.push((id.clone(), format!("lookup {}", index)));
+        shapes.push(Shape::Box);
+        for (side, exprs) in [("input", &lookup.input), ("table", &lookup.table)] {
+            for expr in exprs {
+                for (kind, column, rotation) in expr.queries() {
+                    columns.insert((kind, column));
+                    let text = format!("{} {}", side, rotation_name(rotation));
+                    edges.push((id.clone(), column_id(kind, column), text));
+                }
+                for selector in expr.selectors() {
+                    selectors.insert(selector);
+                    edges.push((
+                        id.clone(),
+                        format!("selector{}", selector),
+                        side.to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    for (kind, column) in columns {
+        let text = format!("{}[{}]", kind, column);
+        nodes.push((column_id(kind, column), text));
+        shapes.push(Shape::Oval);
+    }
+    for selector in selectors {
+        let text = format!("s{}", selector);
+        nodes.push((format!("selector{}", selector), text));
+        shapes.push(Shape::Diamond);
+    }
+
+    let mut stmts = StmtList::new();
+    for ((id, text), node_shape) in nodes.iter().zip(shapes) {
+        let attrs = AttrList::new()
+            .add_pair(label(text))
+            .add_pair(shape(node_shape));
+        stmts = stmts.add_node(identity(id), None, Some(attrs));
+    }
+    for (from, to, text) in &edges {
+        let mut edge = Edge::head_node(identity(from), None).arrow_to_node(identity(to), None);
+        if !text.is_empty() {
+            edge = edge.add_attrpair(label(text));
+        }
+        stmts = stmts.add_edge(edge);
+    }
+
+    GraphBuilder::default()
+        .graph_type(GraphType::DiGraph)
+        .strict(false)
+        .id(identity("gates"))
+        .stmts(stmts)
+        .build()
+        .expect("graph has an id and statements")
+        .to_string()
+}
+
+fn column_id(kind: ColumnKind, column: usize) -> String {
+    format!("{}{}", kind, column)
+}
+
+fn identity(id: &str) -> Identity<'_> {
+    Identity::id(id).expect("ids are alphanumeric")
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    use super::{gates, namespaces};
+    use crate::{example5, inspect::CsInfo};
+
+    #[test]
+    fn test_add2_gates() {
+        let dot = gates(&CsInfo::of::<example5::Add2Circuit<Fp>>());
+
+        assert!(dot.starts_with("digraph gates"), "{}", dot);
+        assert!(
+            dot.contains(r#"gate1[label="gate 1: add 2 constraint";shape=box;]"#),
+            "{}",
+            dot
+        );
+        assert!(dot.contains(r#"constraint1[label="constraint 1\ndegree 2";shape=ellipse;]"#));
+        assert!(dot.contains("gate1->constraint1;"));
+        // s * (a + b + c[next] - res[next])
+        assert!(dot.contains(r#"constraint1->advice0[label="cur";]"#));
+        assert!(dot.contains(r#"constraint1->advice2[label="next";]"#));
+        assert!(dot.contains(r#"constraint1->advice3[label="next";]"#));
+        assert!(dot.contains("constraint1->selector0;"));
+        assert!(dot.contains(r#"selector0[label="s0";shape=diamond;]"#));
+    }
+
+    #[test]
+    fn test_add2_namespaces() {
+        let circuit = example5::Add2Circuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(2)),
+            c: Value::known(Fp::from(3)),
+        };
+        let dot = namespaces(&circuit);
+        assert!(dot.starts_with("digraph circuit"), "{}", dot);
+        for namespace in ["add 2", "intermediate assign", "result assign"] {
+            assert!(dot.contains(&format!("label=\"{}\"", namespace)), "{}", dot);
+        }
+    }
+}
@@ -0,0 +1,580 @@
+// witness and instance values read from json or toml files instead of written
+// into tests. Each circuit has a typed input with its private values and,
+// optionally, the instance columns to check them against; without them the
+// public values an honest prover exposes are used
+use std::{fmt, fs, io, path::Path};
+
+use halo2_proofs::{circuit::Value, pasta::Fp, plonk::Circuit};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    circuits::{self, CircuitError, InputVisitor, Inputs},
+    example1, example2, example3, example4, example5,
+    field::FieldValue,
+    test_fibonacci,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    Json,
+    Toml,
+}
+
+impl InputFormat {
+    pub fn of(path: &Path) -> Result<Self, InputError> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Ok(InputFormat::Json),
+            Some("toml") => Ok(InputFormat::Toml),
+            _ => Err(InputError::UnknownFormat(path.display().to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Io(io::Error),
+    // malformed files and field values that are not canonical
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    UnknownFormat(String),
+    Circuit(CircuitError),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Io(e) => write!(f, "input i/o error: {}", e),
+            InputError::Json(e) => write!(f, "invalid json input: {}", e),
+            InputError::Toml(e) => write!(f, "invalid toml input: {}", e),
+            InputError::UnknownFormat(path) => {
+                write!(f, "input file {:?} is neither .json nor .toml", path)
+            }
+            InputError::Circuit(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl From<io::Error> for InputError {
+    fn from(e: io::Error) -> Self {
+        InputError::Io(e)
+    }
+}
+
+pub fn parse<T: DeserializeOwned>(text: &str, format: InputFormat) -> Result<T, InputError> {
+    match format {
+        InputFormat::Json => serde_json::from_str(text).map_err(InputError::Json),
+        InputFormat::Toml => toml::from_str(text).map_err(InputError::Toml),
+    }
+}
+
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<T, InputError> {
+    let format = InputFormat::of(path)?;
+    parse(&fs::read_to_string(path)?, format)
+}
+
+// the registry hands every circuit name one of these, see `circuits::visit_input`
+pub trait CircuitInput: DeserializeOwned {
+    type Circuit: Circuit<Fp> + Default;
+
+    // the private values by the names the circuit registry gives them, in order
+    const INPUTS: &'static [&'static str];
+
+    // an input without instances from values in `INPUTS` order
+    fn from_values(values: &[Fp]) -> Self;
+
+    fn values(&self) -> Vec<Fp>;
+
+    fn circuit(&self) -> Self::Circuit;
+
+    // the instance columns an honest prover exposes for this witness
+    fn public_inputs(&self) -> Vec<Vec<Fp>>;
+
+    // the instance columns given in the file, if any
+    fn instances(&self) -> Option<&[Vec<FieldValue>]>;
+
+    fn inputs(&self) -> Inputs {
+        Self::INPUTS
+            .iter()
+            .map(|name| name.to_string())
+            .zip(self.values())
+            .collect()
+    }
+}
+
+fn values(columns: &[Vec<FieldValue>]) -> Vec<Vec<Fp>> {
+    columns
+        .iter()
+        .map(|column| column.iter().map(|v| v.0).collect())
+        .collect()
+}
+
+// the circuit and the instance columns to check it against
+pub fn load<I: CircuitInput>(path: &Path) -> Result<(I::Circuit, Vec<Vec<Fp>>), InputError> {
+    let input: I = read(path)?;
+    let instances = input
+        .instances()
+        .map_or_else(|| input.public_inputs(), values);
+    Ok((input.circuit(), instances))
+}
+
+// a registered circuit's input, for tools that pick the circuit by name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredInput {
+    pub inputs: Inputs,
+    // None to use the public values the inputs imply
+    pub instances: Option<Vec<Vec<Fp>>>,
+}
+
+struct Parse<'a> {
+    text: &'a str,
+    format: InputFormat,
+}
+
+impl InputVisitor for Parse<'_> {
+    type Output = Result<RegisteredInput, InputError>;
+
+    fn visit<I: CircuitInput>(self) -> Self::Output {
+        let input: I = parse(self.text, self.format)?;
+        Ok(RegisteredInput {
+            inputs: input.inputs(),
+            instances: input.instances().map(values),
+        })
+    }
+}
+
+// parses `text` as the typed input of the named circuit
+pub fn parse_for(
+    circuit: &str,
+    text: &str,
+    format: InputFormat,
+) -> Result<RegisteredInput, InputError> {
+    circuits::visit_input(circuit, Parse { text, format }).map_err(InputError::Circuit)?
+}
+
+pub fn read_for(circuit: &str, path: &Path) -> Result<RegisteredInput, InputError> {
+    let format = InputFormat::of(path)?;
+    parse_for(circuit, &fs::read_to_string(path)?, format)
+}
+
+// example4: a + b in one instance column
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddInput {
+    pub a: FieldValue,
+    pub b: FieldValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instances: Option<Vec<Vec<FieldValue>>>,
+}
+
+impl CircuitInput for AddInput {
+    type Circuit = example4::AddCircuit<Fp>;
+
+    const INPUTS: &'static [&'static str] = &["a", "b"];
+
+    fn from_values(values: &[Fp]) -> Self {
+        Self {
+            a: FieldValue(values[0]),
+            b: FieldValue(values[1]),
+            instances: None,
+        }
+    }
+
+    fn values(&self) -> Vec<Fp> {
+        vec![self.a.0, self.b.0]
+    }
+
+    fn circuit(&self) -> Self::Circuit {
+        example4::AddCircuit {
+            a: Value::known(self.a.0),
+            b: Value::known(self.b.0),
+        }
+    }
+
+    fn public_inputs(&self) -> Vec<Vec<Fp>> {
+        let (a, b) = (self.a.0, self.b.0);
+        vec![vec![a, b, a + b]]
+    }
+
+    fn instances(&self) -> Option<&[Vec<FieldValue>]> {
+        self.instances.as_deref()
+    }
+}
+
+// example1: a + b, one instance column per public value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddColumnsInput {
+    pub a: FieldValue,
+    pub b: FieldValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instances: Option<Vec<Vec<FieldValue>>>,
+}
+
+impl CircuitInput for AddColumnsInput {
+    type Circuit = example1::AddCircuit<Fp>;
+
+    const INPUTS: &'static [&'static str] = &["a", "b"];
+
+    fn from_values(values: &[Fp]) -> Self {
+        Self {
+            a: FieldValue(values[0]),
+            b: FieldValue(values[1]),
+            instances: None,
+        }
+    }
+
+    fn values(&self) -> Vec<Fp> {
+        vec![self.a.0, self.b.0]
+    }
+
+    fn circuit(&self) -> Self::Circuit {
+        example1::AddCircuit {
+            a: Value::known(self.a.0),
+            b: Value::known(self.b.0),
+        }
+    }
+
+    fn public_inputs(&self) -> Vec<Vec<Fp>> {
+        let (a, b) = (self.a.0, self.b.0);
+        vec![vec![a], vec![b], vec![a + b]]
+    }
+
+    fn instances(&self) -> Option<&[Vec<FieldValue>]> {
+        self.instances.as_deref()
+    }
+}
+
+// example3: input == output in one instance column
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EqualityInput {
+    pub input: FieldValue,
+    pub output: FieldValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instances: Option<Vec<Vec<FieldValue>>>,
+}
+
+impl CircuitInput for EqualityInput {
+    type Circuit = example3::AddCircuit<Fp>;
+
+    const INPUTS: &'static [&'static str] = &["input", "output"];
+
+    fn from_values(values: &[Fp]) -> Self {
+        Self {
+            input: FieldValue(values[0]),
+            output: FieldValue(values[1]),
+            instances: None,
+        }
+    }
+
+    fn values(&self) -> Vec<Fp> {
+        vec![self.input.0, self.output.0]
+    }
+
+    fn circuit(&self) -> Self::Circuit {
+        example3::AddCircuit {
+            input: Value::known(self.input.0),
+            output: Value::known(self.output.0),
+        }
+    }
+
+    fn public_inputs(&self) -> Vec<Vec<Fp>> {
+        vec![vec![self.input.0, self.output.0]]
+    }
+
+    fn instances(&self) -> Option<&[Vec<FieldValue>]> {
+        self.instances.as_deref()
+    }
+}
+
+// example2: input == output, one instance column per public value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EqualityColumnsInput {
+    pub input: FieldValue,
+    pub output: FieldValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instances: Option<Vec<Vec<FieldValue>>>,
+}
+
+impl CircuitInput for EqualityColumnsInput {
+    type Circuit = example2::AddCircuit<Fp>;
+
+    const INPUTS: &'static [&'static str] = &["input", "output"];
+
+    fn from_values(values: &[Fp]) -> Self {
+        Self {
+            input: FieldValue(values[0]),
+            output: FieldValue(values[1]),
+            instances: None,
+        }
+    }
+
+    fn values(&self) -> Vec<Fp> {
+        vec![self.input.0, self.output.0]
+    }
+
+    fn circuit(&self) -> Self::Circuit {
+        example2::AddCircuit {
+            input: Value::known(self.input.0),
+            output: Value::known(self.output.0),
+        }
+    }
+
+    fn public_inputs(&self) -> Vec<Vec<Fp>> {
+        vec![vec![self.input.0], vec![self.output.0]]
+    }
+
+    fn instances(&self) -> Option<&[Vec<FieldValue>]> {
+        self.instances.as_deref()
+    }
+}
+
+// example5: a + b + c over two AddChip rows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Add2Input {
+    pub a: FieldValue,
+    pub b: FieldValue,
+    pub c: FieldValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instances: Option<Vec<Vec<FieldValue>>>,
+}
+
+impl CircuitInput for Add2Input {
+    type Circuit = example5::Add2Circuit<Fp>;
+
+    const INPUTS: &'static [&'static str] = &["a", "b", "c"];
+
+    fn from_values(values: &[Fp]) -> Self {
+        Self {
+            a: FieldValue(values[0]),
+            b: FieldValue(values[1]),
+            c: FieldValue(values[2]),
+            instances: None,
+        }
+    }
+
+    fn values(&self) -> Vec<Fp> {
+        vec![self.a.0, self.b.0, self.c.0]
+    }
+
+    fn circuit(&self) -> Self::Circuit {
+        example5::Add2Circuit {
+            a: Value::known(self.a.0),
+            b: Value::known(self.b.0),
+            c: Value::known(self.c.0),
+        }
+    }
+
+    fn public_inputs(&self) -> Vec<Vec<Fp>> {
+        let (a, b, c) = (self.a.0, self.b.0, self.c.0);
+        vec![vec![a, b, a + b, a + b + c]]
+    }
+
+    fn instances(&self) -> Option<&[Vec<FieldValue>]> {
+        self.instances.as_deref()
+    }
+}
+
+// test_fibonacci: the sequence from a, b up to its 10th element
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FibonacciInput {
+    pub a: FieldValue,
+    pub b: FieldValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instances: Option<Vec<Vec<FieldValue>>>,
+}
+
+impl CircuitInput for FibonacciInput {
+    type Circuit = test_fibonacci::MyCircuit<Fp>;
+
+    const INPUTS: &'static [&'static str] = &["a", "b"];
+
+    fn from_values(values: &[Fp]) -> Self {
+        Self {
+            a: FieldValue(values[0]),
+            b: FieldValue(values[1]),
+            instances: None,
+        }
+    }
+
+    fn values(&self) -> Vec<Fp> {
+        vec![self.a.0, self.b.0]
+    }
+
+    fn circuit(&self) -> Self::Circuit {
+        test_fibonacci::MyCircuit {
+            a: Value::known(self.a.0),
+            b: Value::known(self.b.0),
+        }
+    }
+
+    fn public_inputs(&self) -> Vec<Vec<Fp>> {
+        let (a, b) = (self.a.0, self.b.0);
+        vec![vec![a, b, circuits::fibonacci(a, b, 9)]]
+    }
+
+    fn instances(&self) -> Option<&[Vec<FieldValue>]> {
+        self.instances.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use halo2_proofs::{dev::MockProver, pasta::Fp, plonk::Circuit};
+
+    use super::{
+        load, parse, parse_for, read_for, values, Add2Input, AddInput, CircuitInput,
+        FibonacciInput, InputError, InputFormat,
+    };
+    use crate::{
+        arbitrary,
+        circuits::{self, CircuitVisitor, CIRCUITS},
+        inspect::CsInfo,
+    };
+
+    fn testdata(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/inputs")
+            .join(name)
+    }
+
+    #[test]
+    fn test_load() {
+        let (circuit, instances) = load::<AddInput>(&testdata("add.json")).unwrap();
+        assert_eq!(instances, vec![vec![Fp::from(2), Fp::from(3), Fp::from(5)]]);
+        MockProver::run(4, &circuit, instances)
+            .unwrap()
+            .assert_satisfied();
+
+        let (circuit, instances) = load::<Add2Input>(&testdata("add2.toml")).unwrap();
+        assert_eq!(instances[0][3], Fp::from(6));
+        MockProver::run(4, &circuit, instances)
+            .unwrap()
+            .assert_satisfied();
+
+        let (circuit, instances) = load::<FibonacciInput>(&testdata("fibonacci.json")).unwrap();
+        assert_eq!(instances[0][2], Fp::from(55));
+        MockProver::run(4, &circuit, instances)
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_given_instances() {
+        // a file may claim a wrong result, the circuit then rejects it
+        let text = r#"
+            a = 2
+            b = "0x03"
+            instances = [[2, 3, "6"]]
+        "#;
+        let input: AddInput = parse(text, InputFormat::Toml).unwrap();
+        let instances = values(input.instances().unwrap());
+        assert_eq!(instances, vec![vec![Fp::from(2), Fp::from(3), Fp::from(6)]]);
+        let prover = MockProver::run(4, &input.circuit(), instances).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_reject() {
+        let modulus =
+            "28948022309329048855892746252171976963363056481941560715954676764349967630337";
+        let err = parse::<AddInput>(
+            &format!(r#"{{"a": "{}", "b": 1}}"#, modulus),
+            InputFormat::Json,
+        )
+        .unwrap_err();
+        assert!(matches!(err, InputError::Json(_)));
+        assert!(err.to_string().contains("not below the modulus"), "{}", err);
+
+        let err = parse::<AddInput>("a = \"0xzz\"\nb = 1", InputFormat::Toml).unwrap_err();
+        assert!(err.to_string().contains("invalid field element"), "{}", err);
+
+        // missing, unknown and mistyped fields
+        assert!(parse::<AddInput>(r#"{"a": 1}"#, InputFormat::Json).is_err());
+        assert!(parse::<AddInput>(r#"{"a": 1, "b": 2, "c": 3}"#, InputFormat::Json).is_err());
+        assert!(parse::<AddInput>(r#"{"a": 1, "b": -2}"#, InputFormat::Json).is_err());
+        assert!(parse::<AddInput>(r#"{"a": 1, "b": true}"#, InputFormat::Json).is_err());
+
+        assert!(matches!(
+            read_for("add", Path::new("inputs.yaml")),
+            Err(InputError::UnknownFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_for() {
+        let add2 = read_for("add2", &testdata("add2.toml")).unwrap();
+        let names: Vec<&str> = add2.inputs.keys().map(|k| k.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(add2.instances, None);
+
+        let fibonacci = read_for("fibonacci", &testdata("fibonacci.json")).unwrap();
+        assert_eq!(fibonacci.instances.unwrap()[0][2], Fp::from(55));
+
+        // the column variant takes the same fields
+        let equality = parse_for(
+            "equality-columns",
+            r#"{"input": 3, "output": "3"}"#,
+            InputFormat::Json,
+        )
+        .unwrap();
+        assert_eq!(equality.inputs["output"], Fp::from(3));
+
+        // checked against the named circuit's fields
+        assert!(read_for("add", &testdata("add2.toml")).is_err());
+        assert!(matches!(
+            read_for("nope", &testdata("add.json")),
+            Err(InputError::Circuit(_))
+        ));
+    }
+
+    // public inputs that fill the circuit's instance columns and satisfy it
+    struct Fits;
+
+    impl CircuitVisitor for Fits {
+        type Output = bool;
+
+        fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> bool {
+            CsInfo::of::<C>().num_instance_columns == instances.len()
+                && arbitrary::satisfied(&circuit, instances)
+        }
+    }
+
+    #[test]
+    fn test_every_circuit() {
+        for info in CIRCUITS {
+            let fields: Vec<String> = info
+                .inputs
+                .iter()
+                .map(|name| format!("{:?}: 1", name))
+                .collect();
+            let text = format!("{{{}}}", fields.join(", "));
+            let parsed = parse_for(info.name, &text, InputFormat::Json).unwrap();
+
+            let names: Vec<&str> = parsed.inputs.keys().map(|k| k.as_str()).collect();
+            let mut expected = info.inputs.to_vec();
+            expected.sort();
+            assert_eq!(names, expected, "{}", info.name);
+            assert!(
+                circuits::visit(info.name, &parsed.inputs, Fits).unwrap(),
+                "{} builds instances of the wrong shape",
+                info.name
+            );
+        }
+
+        // example1 exposes a, b and the sum in a column each
+        let add = parse_for("add-columns", r#"{"a": 1, "b": 2}"#, InputFormat::Json).unwrap();
+        let instances = circuits::instances("add-columns", &add.inputs).unwrap();
+        assert_eq!(
+            instances,
+            vec![vec![Fp::from(1)], vec![Fp::from(2)], vec![Fp::from(3)]]
+        );
+    }
+}
@@ -0,0 +1,534 @@
+// read-only view of a ConstraintSystem
+//
+// halo2_proofs 0.2 keeps the gates, queries and permutation of a ConstraintSystem
+// crate private. Its pinned Debug output is the only complete public view, so it
+// is parsed back into a small tree and then into `CsInfo`.
+use std::fmt;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    pasta::Fp,
+    plonk::{Advice, Any, Circuit, Column, ConstraintSystem, Fixed, Instance},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnKind {
+    Advice,
+    Fixed,
+    Instance,
+}
+
+impl fmt::Display for ColumnKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnKind::Advice => write!(f, "advice"),
+            ColumnKind::Fixed => write!(f, "fixed"),
+            ColumnKind::Instance => write!(f, "instance"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Constant(String),
+    Selector(usize),
+    Query {
+        kind: ColumnKind,
+        column: usize,
+        rotation: i32,
+    },
+    Negated(Box<Expr>),
+    Sum(Box<Expr>, Box<Expr>),
+    Product(Box<Expr>, Box<Expr>),
+    Scaled(Box<Expr>, String),
+}
+
+impl Expr {
+    pub fn degree(&self) -> usize {
+        match self {
+            Expr::Constant(_) => 0,
+            Expr::Selector(_) | Expr::Query { .. } => 1,
+            Expr::Negated(a) | Expr::Scaled(a, _) => a.degree(),
+            Expr::Sum(a, b) => a.degree().max(b.degree()),
+            Expr::Product(a, b) => a.degree() + b.degree(),
+        }
+    }
+
+    pub fn selectors(&self) -> Vec<usize> {
+        let mut out = vec![];
+        self.walk(&mut |e| {
+            if let Expr::Selector(s) = e {
+                out.push(*s)
+            }
+        });
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    // (kind, column, rotation) of every cell the expression reads
+    pub fn queries(&self) -> Vec<(ColumnKind, usize, i32)> {
+        let mut out = vec![];
+        self.walk(&mut |e| {
+            if let Expr::Query {
+                kind,
+                column,
+                rotation,
+            } = e
+            {
+                out.push((*kind, *column, *rotation))
+            }
+        });
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    fn walk(&self, f: &mut impl FnMut(&Expr)) {
+        f(self);
+        match self {
+            Expr::Negated(a) | Expr::Scaled(a, _) => a.walk(f),
+            Expr::Sum(a, b) | Expr::Product(a, b) => {
+                a.walk(f);
+                b.walk(f);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Lookup {
+    pub input: Vec<Expr>,
+    pub table: Vec<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gate {
+    pub name: String,
+    // one per constraint, often empty
+    pub constraint_names: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CsInfo {
+    pub num_fixed_columns: usize,
+    pub num_advice_columns: usize,
+    pub num_instance_columns: usize,
+    pub num_selectors: usize,
+    // one entry per constraint polynomial, in the order the gates were created
+    pub constraints: Vec<Expr>,
+    // gate names are left out of the pinned output and read from the full Debug
+    pub gates: Vec<Gate>,
+    pub advice_queries: Vec<(usize, i32)>,
+    pub instance_queries: Vec<(usize, i32)>,
+    pub fixed_queries: Vec<(usize, i32)>,
+    // columns with equality enabled
+    pub permutation: Vec<(ColumnKind, usize)>,
+    pub lookups: Vec<Lookup>,
+    // fixed columns reserved for global constants
+    pub constants: Vec<usize>,
+    pub degree: usize,
+    pub blinding_factors: usize,
+    pub minimum_rows: usize,
+}
+
+impl CsInfo {
+    pub fn of<C: Circuit<Fp>>() -> Self {
+        let mut cs = ConstraintSystem::default();
+        C::configure(&mut cs);
+        Self::from_cs(&cs)
+    }
+
+    pub fn from_cs<F: Field>(cs: &ConstraintSystem<F>) -> Self {
+        let pinned = format!("{:?}", cs.pinned());
+        let node = parse_debug(&pinned).expect("pinned constraint system is valid debug output");
+        let full = format!("{:?}", cs);
+        let full = parse_debug(&full).expect("constraint system is valid debug output");
+
+        let usize_field = |name: &str| node.field(name).as_usize();
+        let queries = |name: &str| {
+            node.field(name)
+                .items()
+                .iter()
+                .map(|q| {
+                    (
+                        q.items()[0].field("index").as_usize(),
+                        rotation(&q.items()[1]),
+                    )
+                })
+                .collect()
+        };
+
+        CsInfo {
+            num_fixed_columns: usize_field("num_fixed_columns"),
+            num_advice_columns: usize_field("num_advice_columns"),
+            num_instance_columns: usize_field("num_instance_columns"),
+            num_selectors: usize_field("num_selectors"),
+            constraints: node.field("gates").items().iter().map(expr).collect(),
+            gates: full
+                .field("gates")
+                .items()
+                .iter()
+                .map(|g| Gate {
+                    name: g.field("name").name().to_string(),
+                    constraint_names: g
+                        .field("constraint_names")
+                        .items()
+                        .iter()
+                        .map(|n| n.name().to_string())
+                        .collect(),
+                })
+                .collect(),
+            advice_queries: queries("advice_queries"),
+            instance_queries: queries("instance_queries"),
+            fixed_queries: queries("fixed_queries"),
+            permutation: node
+                .field("permutation")
+                .field("columns")
+                .items()
+                .iter()
+                .map(column)
+                .collect(),
+            lookups: node
+                .field("lookups")
+                .items()
+                .iter()
+                .map(|l| Lookup {
+                    input: l
+                        .field("input_expressions")
+                        .items()
+                        .iter()
+                        .map(expr)
+                        .collect(),
+                    table: l
+                        .field("table_expressions")
+                        .items()
+                        .iter()
+                        .map(expr)
+                        .collect(),
+                })
+                .collect(),
+            constants: node
+                .field("constants")
+                .items()
+                .iter()
+                .map(|c| c.field("index").as_usize())
+                .collect(),
+            degree: cs.degree(),
+            blinding_factors: cs.blinding_factors(),
+            minimum_rows: cs.minimum_rows(),
+        }
+    }
+
+    // the gate each entry of `constraints` belongs to, by index into `gates`
+    pub fn constraint_gates(&self) -> Vec<usize> {
+        let gates = self.gates.iter().enumerate();
+        gates
+            .flat_map(|(index, gate)| gate.constraint_names.iter().map(move |_| index))
+            .collect()
+    }
+
+    // column handles matching this system's indices, `Column` has no public constructor
+    pub fn advice_columns(&self) -> Vec<Column<Advice>> {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        (0..self.num_advice_columns)
+            .map(|_| cs.advice_column())
+            .collect()
+    }
+
+    pub fn fixed_columns(&self) -> Vec<Column<Fixed>> {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        (0..self.num_fixed_columns)
+            .map(|_| cs.fixed_column())
+            .collect()
+    }
+
+    pub fn instance_columns(&self) -> Vec<Column<Instance>> {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        (0..self.num_instance_columns)
+            .map(|_| cs.instance_column())
+            .collect()
+    }
+
+    pub fn constant_columns(&self) -> Vec<Column<Fixed>> {
+        let fixed = self.fixed_columns();
+        self.constants.iter().map(|i| fixed[*i]).collect()
+    }
+}
+
+// cur, next and prev for rotations 0, 1 and -1, the offset otherwise
+pub fn rotation_name(rotation: i32) -> String {
+    match rotation {
+        0 => "cur".to_string(),
+        1 => "next".to_string(),
+        -1 => "prev".to_string(),
+        n => format!("{:+}", n),
+    }
+}
+
+// (kind, index) of a column handle, `Column::index` is crate private in halo2_proofs 0.2
+pub fn column_id<C: Into<Column<Any>>>(column: C) -> (ColumnKind, usize) {
+    let column: Column<Any> = column.into();
+    let text = format!("{:?}", column);
+    let node = parse_debug(&text).expect("column is valid debug output");
+    self::column(&node)
+}
+
+pub(crate) fn column(node: &Node) -> (ColumnKind, usize) {
+    let kind = match node.field("column_type").name() {
+        "Advice" => ColumnKind::Advice,
+        "Fixed" => ColumnKind::Fixed,
+        "Instance" => ColumnKind::Instance,
+        other => panic!("unknown column type {}", other),
+    };
+    (kind, node.field("index").as_usize())
+}
+
+fn rotation(node: &Node) -> i32 {
+    node.items()[0]
+        .name()
+        .parse()
+        .expect("rotation is an integer")
+}
+
+fn expr(node: &Node) -> Expr {
+    let args = node.items();
+    let query = |kind| Expr::Query {
+        kind,
+        column: node.field("column_index").as_usize(),
+        rotation: rotation(node.field("rotation")),
+    };
+
+    match node.name() {
+        "Constant" => Expr::Constant(args[0].name().to_string()),
+        "Selector" => Expr::Selector(args[0].items()[0].as_usize()),
+        "Fixed" => query(ColumnKind::Fixed),
+        "Advice" => query(ColumnKind::Advice),
+        "Instance" => query(ColumnKind::Instance),
+        "Negated" => Expr::Negated(Box::new(expr(&args[0]))),
+        "Sum" => Expr::Sum(Box::new(expr(&args[0])), Box::new(expr(&args[1]))),
+        "Product" => Expr::Product(Box::new(expr(&args[0])), Box::new(expr(&args[1]))),
+        "Scaled" => Expr::Scaled(Box::new(expr(&args[0])), args[1].name().to_string()),
+        other => panic!("unknown expression {}", other),
+    }
+}
+
+pub(crate) fn parse_debug(text: &str) -> Option<Node> {
+    Parser::new(text).node()
+}
+
+// Debug output as a tree: `Name { field: node }`, `Name(node, ..)`, `[node, ..]` or an atom
+#[derive(Debug)]
+pub(crate) enum Node {
+    Struct(String, Vec<(String, Node)>),
+    Tuple(String, Vec<Node>),
+    List(Vec<Node>),
+    Atom(String),
+}
+
+impl Node {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Node::Struct(name, _) | Node::Tuple(name, _) | Node::Atom(name) => name,
+            Node::List(_) => "",
+        }
+    }
+
+    pub(crate) fn field(&self, name: &str) -> &Node {
+        match self {
+            Node::Struct(_, fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, node)| node)
+                .unwrap_or_else(|| panic!("missing field {}", name)),
+            _ => panic!("{} is not a struct", self.name()),
+        }
+    }
+
+    pub(crate) fn items(&self) -> &[Node] {
+        match self {
+            Node::Tuple(_, items) | Node::List(items) => items,
+            _ => &[],
+        }
+    }
+
+    pub(crate) fn as_usize(&self) -> usize {
+        self.name().parse().expect("integer")
+    }
+}
+
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { rest: text }
+    }
+
+    fn skip_ws(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        if self.rest.starts_with(c) {
+            self.rest = &self.rest[c.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn word(&mut self) -> Option<String> {
+        self.skip_ws();
+        if let Some(rest) = self.rest.strip_prefix('"') {
+            // quoted string, keep escapes as they are
+            let mut end = 0;
+            let bytes = rest.as_bytes();
+            while end < bytes.len() && bytes[end] != b'"' {
+                end += if bytes[end] == b'\\' { 2 } else { 1 };
+            }
+            let word = rest[..end].to_string();
+            self.rest = &rest[(end + 1).min(rest.len())..];
+            return Some(word);
+        }
+
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            return None;
+        }
+        let word = self.rest[..end].to_string();
+        self.rest = &self.rest[end..];
+
+        // drop generic arguments, as in `PhantomData<T>`
+        if self.rest.starts_with('<') {
+            let mut depth = 0;
+            for (i, c) in self.rest.char_indices() {
+                match c {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    _ => {}
+                }
+                if depth == 0 {
+                    self.rest = &self.rest[i + 1..];
+                    break;
+                }
+            }
+        }
+        Some(word)
+    }
+
+    fn list(&mut self, close: char) -> Option<Vec<Node>> {
+        let mut items = vec![];
+        while !self.eat(close) {
+            items.push(self.node()?);
+            self.eat(',');
+        }
+        Some(items)
+    }
+
+    fn node(&mut self) -> Option<Node> {
+        if self.eat('[') {
+            return self.list(']').map(Node::List);
+        }
+        if self.eat('(') {
+            return self
+                .list(')')
+                .map(|items| Node::Tuple(String::new(), items));
+        }
+
+        let name = self.word()?;
+        if self.eat('{') {
+            let mut fields = vec![];
+            while !self.eat('}') {
+                let field = self.word()?;
+                if !self.eat(':') {
+                    return None;
+                }
+                fields.push((field, self.node()?));
+                self.eat(',');
+            }
+            return Some(Node::Struct(name, fields));
+        }
+        if self.eat('(') {
+            return self.list(')').map(|items| Node::Tuple(name, items));
+        }
+        Some(Node::Atom(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::Fp, plonk::ConstraintSystem};
+
+    use super::{column_id, ColumnKind, CsInfo, Expr};
+    use crate::{example1, example5, test_fibonacci};
+
+    #[test]
+    fn test_add2() {
+        let info = CsInfo::of::<example5::Add2Circuit<Fp>>();
+
+        assert_eq!(info.num_advice_columns, 4);
+        assert_eq!(info.num_instance_columns, 1);
+        assert_eq!(info.num_selectors, 2);
+        assert_eq!(info.constraints.len(), 2);
+        assert_eq!(info.permutation.len(), 4);
+        assert!(info.lookups.is_empty());
+        let names: Vec<&str> = info.gates.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["addition gate", "add 2 constraint"]);
+        assert_eq!(info.constraint_gates(), vec![0, 1]);
+
+        // s * (a + b + c[next] - res[next])
+        let add2 = &info.constraints[1];
+        assert_eq!(add2.degree(), 2);
+        assert_eq!(
+            add2.queries(),
+            vec![
+                (ColumnKind::Advice, 0, 0),
+                (ColumnKind::Advice, 1, 0),
+                (ColumnKind::Advice, 2, 1),
+                (ColumnKind::Advice, 3, 1),
+            ]
+        );
+        assert!(matches!(add2, Expr::Product(..)));
+    }
+
+    #[test]
+    fn test_instances() {
+        let info = CsInfo::of::<example1::AddCircuit<Fp>>();
+        assert_eq!(info.num_instance_columns, 3);
+        assert_eq!(
+            info.permutation
+                .iter()
+                .filter(|(kind, _)| *kind == ColumnKind::Instance)
+                .count(),
+            3
+        );
+
+        let info = CsInfo::of::<test_fibonacci::MyCircuit<Fp>>();
+        assert_eq!(info.advice_queries, vec![(0, 0), (1, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn test_column_id() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let _ = cs.advice_column();
+        let advice = cs.advice_column();
+        let fixed = cs.fixed_column();
+
+        assert_eq!(column_id(advice), (ColumnKind::Advice, 1));
+        assert_eq!(column_id(fixed), (ColumnKind::Fixed, 0));
+
+        cs.enable_constant(fixed);
+        let info = CsInfo::from_cs(&cs);
+        assert_eq!(info.constants, vec![0]);
+        assert_eq!(info.constant_columns(), vec![fixed]);
+    }
+}
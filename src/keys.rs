@@ -0,0 +1,377 @@
+// on-disk params, verifying key fingerprints and an in-memory proving key cache
+//
+// Only params persist. halo2_proofs 0.2 can serialize `Params` but not
+// `VerifyingKey` or `ProvingKey`, so a vk file holds just the key's fingerprint
+// and every process runs keygen again. The fingerprint pins which circuit a key
+// belongs to: `keygen_vk_checked` and the cache reject a circuit whose key does
+// not match it.
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_vk, Circuit, Error, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+};
+
+use crate::prover;
+
+const VK_MAGIC: &[u8; 4] = b"H2VK";
+
+pub type Fingerprint = [u8; 32];
+
+pub type SharedKeys = (Arc<Params<EqAffine>>, Arc<ProvingKey<EqAffine>>);
+
+#[derive(Debug)]
+pub enum KeyError {
+    Io(io::Error),
+    Plonk(Error),
+    // the key on disk was generated for a different circuit or k
+    CircuitMismatch {
+        expected: Fingerprint,
+        found: Fingerprint,
+    },
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::Io(e) => write!(f, "key i/o error: {}", e),
+            KeyError::Plonk(e) => write!(f, "keygen failed: {}", e),
+            KeyError::CircuitMismatch { .. } => {
+                write!(f, "verifying key on disk belongs to a different circuit")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+impl From<io::Error> for KeyError {
+    fn from(e: io::Error) -> Self {
+        KeyError::Io(e)
+    }
+}
+
+impl From<Error> for KeyError {
+    fn from(e: Error) -> Self {
+        KeyError::Plonk(e)
+    }
+}
+
+// hash of the pinned verifying key, i.e. of the circuit shape, fixed columns and k
+pub fn fingerprint(vk: &VerifyingKey<EqAffine>) -> Fingerprint {
+    let pinned = format!("{:?}", vk.pinned());
+    let hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(b"halo2-example-vk")
+        .hash(pinned.as_bytes());
+
+    let mut out = [0u8; 32];
+    out.copy_from_slice(hash.as_bytes());
+    out
+}
+
+// stable name of a circuit type, usable as a file name. Generic parameters are
+// kept, so `Foo<Fp>` and `Foo<Fq>` get different ids
+pub fn circuit_id<C>() -> String {
+    std::any::type_name::<C>()
+        .replace("::", "-")
+        .replace('<', "[")
+        .replace('>', "]")
+        .replace(", ", ",")
+}
+
+pub fn write_params(params: &Params<EqAffine>, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    params.write(&mut file)?;
+    file.flush()
+}
+
+// params hold 2^k generators
+pub fn params_k(params: &Params<EqAffine>) -> u32 {
+    params.get_g().len().trailing_zeros()
+}
+
+pub fn read_params(path: impl AsRef<Path>) -> io::Result<Params<EqAffine>> {
+    let mut file = io::BufReader::new(fs::File::open(path)?);
+    Params::read(&mut file)
+}
+
+// the contents of a vk file: magic and fingerprint
+pub fn vk_fingerprint_bytes(vk: &VerifyingKey<EqAffine>) -> Vec<u8> {
+    [&VK_MAGIC[..], &fingerprint(vk)].concat()
+}
+
+pub fn write_vk_fingerprint(vk: &VerifyingKey<EqAffine>, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, vk_fingerprint_bytes(vk))
+}
+
+pub fn read_vk_fingerprint(path: impl AsRef<Path>) -> io::Result<Fingerprint> {
+    let mut file = fs::File::open(path)?;
+
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)?;
+    if &magic != VK_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a verifying key file",
+        ));
+    }
+
+    let mut found = [0u8; 32];
+    file.read_exact(&mut found)?;
+    Ok(found)
+}
+
+// runs keygen for `circuit` and checks the key against the fingerprint at `path`
+pub fn keygen_vk_checked<C: Circuit<Fp>>(
+    path: impl AsRef<Path>,
+    params: &Params<EqAffine>,
+    circuit: &C,
+) -> Result<VerifyingKey<EqAffine>, KeyError> {
+    let found = read_vk_fingerprint(path)?;
+
+    let vk = keygen_vk(params, &circuit.without_witnesses())?;
+    let expected = fingerprint(&vk);
+    if found != expected {
+        return Err(KeyError::CircuitMismatch { expected, found });
+    }
+
+    Ok(vk)
+}
+
+// keygen for `circuit`, writing the fingerprint at `vk_path` when there is none
+// yet and rejecting one for another circuit
+pub fn keygen_fingerprinted<C: Circuit<Fp>>(
+    vk_path: impl AsRef<Path>,
+    params: &Params<EqAffine>,
+    circuit: &C,
+) -> Result<ProvingKey<EqAffine>, KeyError> {
+    let pk = prover::keygen(params, circuit)?;
+    let expected = fingerprint(pk.get_vk());
+
+    match read_vk_fingerprint(&vk_path) {
+        Ok(found) if found != expected => Err(KeyError::CircuitMismatch { expected, found }),
+        Ok(_) => Ok(pk),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            write_vk_fingerprint(pk.get_vk(), &vk_path)?;
+            Ok(pk)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+// params are kept on disk per k. Proving keys are kept in memory per circuit and
+// k, and their fingerprints are written next to the params. Only repeated proving
+// within one process skips keygen: a new process, or a new cache over the same
+// dir, runs keygen again for every circuit and k
+pub struct KeyCache {
+    dir: PathBuf,
+    params: HashMap<u32, Arc<Params<EqAffine>>>,
+    keys: HashMap<(String, u32), Arc<ProvingKey<EqAffine>>>,
+}
+
+impl KeyCache {
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            params: HashMap::new(),
+            keys: HashMap::new(),
+        })
+    }
+
+    pub fn params_path(&self, k: u32) -> PathBuf {
+        self.dir.join(format!("params-k{}.bin", k))
+    }
+
+    pub fn vk_path(&self, circuit_id: &str, k: u32) -> PathBuf {
+        self.dir.join(format!("{}-k{}.vk", circuit_id, k))
+    }
+
+    pub fn params(&mut self, k: u32) -> io::Result<&Params<EqAffine>> {
+        if !self.params.contains_key(&k) {
+            let path = self.params_path(k);
+            let params = if path.exists() {
+                read_params(&path)?
+            } else {
+                let params = Params::new(k);
+                write_params(&params, &path)?;
+                params
+            };
+            self.params.insert(k, Arc::new(params));
+        }
+
+        Ok(&self.params[&k])
+    }
+
+    pub fn shared_params(&mut self, k: u32) -> io::Result<Arc<Params<EqAffine>>> {
+        self.params(k)?;
+        Ok(self.params[&k].clone())
+    }
+
+    pub fn get_or_keygen<C: Circuit<Fp>>(
+        &mut self,
+        k: u32,
+        circuit: &C,
+    ) -> Result<(&Params<EqAffine>, &ProvingKey<EqAffine>), KeyError> {
+        let key = (circuit_id::<C>(), k);
+
+        if !self.keys.contains_key(&key) {
+            let vk_path = self.vk_path(&key.0, k);
+            let pk = keygen_fingerprinted(vk_path, self.params(k)?, circuit)?;
+            self.keys.insert(key.clone(), Arc::new(pk));
+        }
+
+        Ok((&self.params[&k], &self.keys[&key]))
+    }
+
+    // `get_or_keygen` for keys that outlive the borrow of the cache, e.g. on other threads
+    pub fn get_or_keygen_shared<C: Circuit<Fp>>(
+        &mut self,
+        k: u32,
+        circuit: &C,
+    ) -> Result<SharedKeys, KeyError> {
+        self.get_or_keygen(k, circuit)?;
+        let key = (circuit_id::<C>(), k);
+
+        Ok((self.params[&k].clone(), self.keys[&key].clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use halo2_proofs::{
+        circuit::Value,
+        pasta::{Fp, Fq},
+        poly::commitment::Params,
+    };
+
+    use super::{
+        circuit_id, fingerprint, keygen_vk_checked, params_k, read_params, read_vk_fingerprint,
+        write_params, write_vk_fingerprint, KeyCache, KeyError,
+    };
+    use crate::{example3, example4, prover, temp_dir::TempDir};
+
+    fn equality_circuit() -> example3::AddCircuit<Fp> {
+        example3::AddCircuit {
+            input: Value::known(Fp::from(1)),
+            output: Value::known(Fp::from(1)),
+        }
+    }
+
+    #[test]
+    fn test_params_round_trip() {
+        let dir = TempDir::new("params");
+        let path = dir.path().join("params.bin");
+
+        let params = Params::new(4);
+        write_params(&params, &path).unwrap();
+        let read = read_params(&path).unwrap();
+        assert_eq!(params_k(&read), 4);
+
+        let (mut a, mut b) = (vec![], vec![]);
+        params.write(&mut a).unwrap();
+        read.write(&mut b).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_vk_round_trip() {
+        let dir = TempDir::new("vk");
+        let path = dir.path().join("equality.vk");
+
+        let circuit = equality_circuit();
+        let (params, pk) = prover::setup(4, &circuit).unwrap();
+        write_vk_fingerprint(pk.get_vk(), &path).unwrap();
+
+        let vk = keygen_vk_checked(&path, &params, &circuit).unwrap();
+        assert_eq!(fingerprint(&vk), fingerprint(pk.get_vk()));
+    }
+
+    #[test]
+    fn test_vk_wrong_circuit() {
+        let dir = TempDir::new("vk-wrong");
+        let path = dir.path().join("equality.vk");
+
+        let (params, pk) = prover::setup(4, &equality_circuit()).unwrap();
+        write_vk_fingerprint(pk.get_vk(), &path).unwrap();
+
+        let add = example4::AddCircuit::<Fp>::default();
+        let res = keygen_vk_checked(&path, &params, &add);
+        assert!(matches!(res, Err(KeyError::CircuitMismatch { .. })));
+    }
+
+    #[test]
+    fn test_cache() {
+        let dir = TempDir::new("cache");
+        let circuit = equality_circuit();
+
+        let mut cache = KeyCache::new(dir.path()).unwrap();
+        let first = {
+            let (_, pk) = cache.get_or_keygen(4, &circuit).unwrap();
+            fingerprint(pk.get_vk())
+        };
+        assert!(cache.params_path(4).exists());
+        assert!(cache
+            .vk_path(&circuit_id::<example3::AddCircuit<Fp>>(), 4)
+            .exists());
+
+        // a fresh cache reads the params back and regenerates the same key
+        let mut cache = KeyCache::new(dir.path()).unwrap();
+        let (params, pk) = cache.get_or_keygen(4, &circuit).unwrap();
+        assert_eq!(fingerprint(pk.get_vk()), first);
+
+        let public_input = [Fp::from(1), Fp::from(1)];
+        let instances: &[&[Fp]] = &[&public_input[..]];
+        let proof =
+            prover::prove(params, pk, std::slice::from_ref(&circuit), &[instances]).unwrap();
+        assert!(prover::verify(params, pk.get_vk(), &[instances], &proof).is_ok());
+    }
+
+    #[test]
+    fn test_cache_wrong_vk() {
+        let dir = TempDir::new("cache-wrong");
+        let mut cache = KeyCache::new(dir.path()).unwrap();
+
+        // plant a vk of another circuit where the equality circuit's vk belongs
+        let (_, add_pk) = prover::setup(4, &example4::AddCircuit::<Fp>::default()).unwrap();
+        let path = cache.vk_path(&circuit_id::<example3::AddCircuit<Fp>>(), 4);
+        write_vk_fingerprint(add_pk.get_vk(), &path).unwrap();
+
+        let res = cache.get_or_keygen(4, &equality_circuit());
+        assert!(matches!(res, Err(KeyError::CircuitMismatch { .. })));
+        // and leave it for whoever has to look at it
+        assert_eq!(
+            read_vk_fingerprint(&path).unwrap(),
+            fingerprint(add_pk.get_vk())
+        );
+
+        fs::write(&path, b"garbage").unwrap();
+        let mut cache = KeyCache::new(dir.path()).unwrap();
+        let res = cache.get_or_keygen(4, &equality_circuit());
+        assert!(matches!(res, Err(KeyError::Io(_))));
+    }
+
+    #[test]
+    fn test_circuit_id() {
+        let id = circuit_id::<example3::AddCircuit<Fp>>();
+        assert!(
+            id.starts_with("halo2_example-example3-AddCircuit["),
+            "{}",
+            id
+        );
+        assert!(id.ends_with("Fp]"), "{}", id);
+        assert_ne!(id, circuit_id::<example3::AddCircuit<Fq>>());
+    }
+}
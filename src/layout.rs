@@ -0,0 +1,478 @@
+// the layout of a circuit: which cells each row assigns, the selectors it enables,
+// the regions covering it and where copy constraints start and end. Rendered as
+// text for terminals and tests, or as svg or an html page to look at in a browser
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Write,
+    str::FromStr,
+};
+
+use halo2_proofs::{
+    pasta::Fp,
+    plonk::{Circuit, Error},
+};
+
+use crate::{
+    field::fp_to_hex,
+    inspect::{ColumnKind, CsInfo},
+    recorder::{self, Position, Recording},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Svg,
+    Html,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "svg" => Ok(Format::Svg),
+            "html" => Ok(Format::Html),
+            other => Err(format!(
+                "unknown layout format {:?}, expected text, svg or html",
+                other
+            )),
+        }
+    }
+}
+
+const CELL_WIDTH: usize = 28;
+const CELL_HEIGHT: usize = 18;
+const HEADER: usize = 24;
+
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub info: CsInfo,
+    pub recording: Recording,
+}
+
+impl Layout {
+    pub fn of<C: Circuit<Fp>>(circuit: &C, instances: &[Vec<Fp>]) -> Result<Self, Error> {
+        Ok(Self {
+            info: CsInfo::of::<C>(),
+            recording: recorder::record(circuit, instances)?,
+        })
+    }
+
+    // (label, kind, index) of every column, selectors last with no kind
+    pub fn columns(&self) -> Vec<(String, Option<ColumnKind>, usize)> {
+        let info = &self.info;
+        let groups = [
+            ("a", Some(ColumnKind::Advice), info.num_advice_columns),
+            ("f", Some(ColumnKind::Fixed), info.num_fixed_columns),
+            ("i", Some(ColumnKind::Instance), info.num_instance_columns),
+            ("s", None, info.num_selectors),
+        ];
+        groups
+            .iter()
+            .flat_map(|(prefix, kind, n)| {
+                (0..*n).map(move |i| (format!("{}{}", prefix, i), *kind, i))
+            })
+            .collect()
+    }
+
+    // regions covering `row`, by index
+    pub fn regions_at(&self, row: usize) -> Vec<usize> {
+        let regions = self.recording.regions.iter().enumerate();
+        regions
+            .filter(|(_, region)| matches!(region.rows, Some((start, end)) if start <= row && row <= end))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    pub fn to_text(&self) -> String {
+        let recording = &self.recording;
+        let assigned: HashSet<Position> = recording.cells.iter().map(|c| c.position).collect();
+        let copied: HashSet<Position> = recording
+            .copies
+            .iter()
+            .flat_map(|(l, r)| [*l, *r])
+            .collect();
+        let enabled: HashSet<(usize, usize)> = recording
+            .selectors
+            .iter()
+            .map(|s| (s.selector, s.row))
+            .collect();
+
+        let columns = self.columns();
+        let rows = recording.rows();
+        let width = rows.saturating_sub(1).to_string().len().max(3);
+
+        let mut out = String::new();
+        write!(out, "{:>width$} ", "row").unwrap();
+        let mut last_kind = None;
+        for (label, kind, _) in &columns {
+            if last_kind != Some(*kind) {
+                out.push_str(" |");
+                last_kind = Some(*kind);
+            }
+            write!(out, "{:>4}", label).unwrap();
+        }
+        out.push_str(" | regions\n");
+
+        for row in 0..rows {
+            write!(out, "{:>width$} ", row).unwrap();
+            let mut last_kind = None;
+            for (_, kind, index) in &columns {
+                if last_kind != Some(*kind) {
+                    out.push_str(" |");
+                    last_kind = Some(*kind);
+                }
+                let glyph = match kind {
+                    Some(kind) => {
+                        let position = Position {
+                            kind: *kind,
+                            column: *index,
+                            row,
+                        };
+                        if copied.contains(&position) {
+                            '='
+                        } else if assigned.contains(&position) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    }
+                    None if enabled.contains(&(*index, row)) => '1',
+                    None => '.',
+                };
+                write!(out, "{:>4}", glyph).unwrap();
+            }
+
+            let regions: Vec<String> = self
+                .regions_at(row)
+                .into_iter()
+                .map(|index| {
+                    let region = &recording.regions[index];
+                    match region.rows {
+                        Some((start, _)) if start == row => format!("{} '{}'", index, region.name),
+                        _ => index.to_string(),
+                    }
+                })
+                .collect();
+            if regions.is_empty() {
+                out.push_str(" |\n");
+            } else {
+                writeln!(out, " | {}", regions.join(", ")).unwrap();
+            }
+        }
+        out.push_str("\n# assigned  = copy endpoint  . empty  1 selector enabled\n");
+
+        if !recording.copies.is_empty() {
+            out.push_str("\ncopies\n");
+            for (left, right) in &recording.copies {
+                writeln!(out, "  {} = {}", left, right).unwrap();
+            }
+        }
+        out
+    }
+
+    // `title` heads the svg and html output, text has none
+    pub fn render(&self, format: Format, title: &str) -> String {
+        match format {
+            Format::Text => self.to_text(),
+            Format::Svg => self.to_svg(title),
+            Format::Html => self.to_html(title),
+        }
+    }
+
+    // one cell per column and row, sized to fit. Cells, selectors, copy endpoints
+    // and regions carry a <title> that browsers show on hover
+    pub fn to_svg(&self, title: &str) -> String {
+        let recording = &self.recording;
+        let columns = self.columns();
+        let rows = recording.rows();
+        let column_of = |kind: Option<ColumnKind>, index: usize| {
+            columns
+                .iter()
+                .position(|(_, k, i)| *k == kind && *i == index)
+                .expect("recorded column exists")
+        };
+
+        let labels: Vec<String> = recording
+            .regions
+            .iter()
+            .enumerate()
+            .map(|(index, region)| format!("{} '{}'", index, region.name))
+            .collect();
+        let left = 8 * rows.saturating_sub(1).to_string().len() + 16;
+        let grid_width = columns.len() * CELL_WIDTH;
+        let label_width = labels.iter().map(|l| l.len()).max().unwrap_or(0) * 7 + 16;
+        let (width, height) = (
+            left + grid_width + label_width,
+            HEADER + rows * CELL_HEIGHT + 8,
+        );
+        let x = |column: usize| left + column * CELL_WIDTH;
+        let y = |row: usize| HEADER + row * CELL_HEIGHT;
+
+        let mut partners: BTreeMap<Position, Vec<Position>> = BTreeMap::new();
+        for (left, right) in &recording.copies {
+            partners.entry(*left).or_default().push(*right);
+            partners.entry(*right).or_default().push(*left);
+        }
+        let copied_to = |position: &Position| -> String {
+            let partners = partners
+                .get(position)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            partners
+                .iter()
+                .map(|p| format!("\ncopied to {}", p))
+                .collect()
+        };
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="monospace" font-size="11">"#,
+            width, height
+        )
+        .unwrap();
+        writeln!(out, "<title>{}</title>", escape(title)).unwrap();
+        out.push_str("<style>rect.cell:hover, rect.selector:hover { stroke: #000; stroke-width: 2 }</style>\n");
+
+        for (i, (label, kind, _)) in columns.iter().enumerate() {
+            writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                x(i),
+                HEADER,
+                CELL_WIDTH,
+                rows * CELL_HEIGHT,
+                colors(*kind).0
+            )
+            .unwrap();
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+                x(i) + CELL_WIDTH / 2,
+                HEADER - 8,
+                label
+            )
+            .unwrap();
+        }
+        for row in 0..rows {
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#,
+                left - 6,
+                y(row) + 13,
+                row
+            )
+            .unwrap();
+        }
+
+        for cell in &recording.cells {
+            let position = cell.position;
+            let column = column_of(Some(position.kind), position.column);
+            let mut tip = position.to_string();
+            if !cell.name.is_empty() {
+                write!(tip, " '{}'", cell.name).unwrap();
+            }
+            if let Some(value) = &cell.value {
+                write!(tip, " = {}", fp_to_hex(value)).unwrap();
+            }
+            tip.push_str(&copied_to(&position));
+            writeln!(
+                out,
+                r#"<rect class="cell" x="{}" y="{}" width="{}" height="{}" fill="{}"><title>{}</title></rect>"#,
+                x(column) + 2,
+                y(position.row) + 2,
+                CELL_WIDTH - 4,
+                CELL_HEIGHT - 4,
+                colors(Some(position.kind)).1,
+                escape(&tip)
+            )
+            .unwrap();
+        }
+        for selector in &recording.selectors {
+            writeln!(
+                out,
+                r#"<rect class="selector" x="{}" y="{}" width="{}" height="{}" fill="{}"><title>s{} enabled at row {}</title></rect>"#,
+                x(column_of(None, selector.selector)) + 2,
+                y(selector.row) + 2,
+                CELL_WIDTH - 4,
+                CELL_HEIGHT - 4,
+                colors(None).1,
+                selector.selector,
+                selector.row
+            )
+            .unwrap();
+        }
+        for position in partners.keys() {
+            let column = column_of(Some(position.kind), position.column);
+            writeln!(
+                out,
+                r##"<circle cx="{}" cy="{}" r="3" fill="#000"><title>{}</title></circle>"##,
+                x(column) + CELL_WIDTH / 2,
+                y(position.row) + CELL_HEIGHT / 2,
+                escape(&format!("{}{}", position, copied_to(position)))
+            )
+            .unwrap();
+        }
+
+        for (index, region) in recording.regions.iter().enumerate() {
+            let Some((start, end)) = region.rows else {
+                continue;
+            };
+            let cells = recording.cells.iter().filter(|c| c.region == Some(index));
+            let selectors = recording
+                .selectors
+                .iter()
+                .filter(|s| s.region == Some(index));
+            let used: BTreeSet<usize> = cells
+                .map(|c| column_of(Some(c.position.kind), c.position.column))
+                .chain(selectors.map(|s| column_of(None, s.selector)))
+                .collect();
+            let (first, last) = (used.first().unwrap(), used.last().unwrap());
+
+            writeln!(
+                out,
+                r##"<rect class="region" x="{}" y="{}" width="{}" height="{}" fill="none" stroke="#444" stroke-dasharray="4 2"><title>{}</title></rect>"##,
+                x(*first),
+                y(start),
+                (last - first + 1) * CELL_WIDTH,
+                (end - start + 1) * CELL_HEIGHT,
+                escape(&labels[index])
+            )
+            .unwrap();
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                left + grid_width + 8,
+                y(start) + 13,
+                escape(&labels[index])
+            )
+            .unwrap();
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+
+    // a page with nothing to fetch, the svg inline
+    pub fn to_html(&self, title: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+             <style>body {{ font-family: sans-serif; margin: 24px }}</style>\n</head>\n<body>\n\
+             <h1>{0}</h1>\n<p>hover a cell, selector, copy endpoint or region for details</p>\n\
+             {1}</body>\n</html>\n",
+            escape(title),
+            self.to_svg(title)
+        )
+    }
+}
+
+// (column background, cell fill) by column kind, selectors have no kind
+fn colors(kind: Option<ColumnKind>) -> (&'static str, &'static str) {
+    match kind {
+        Some(ColumnKind::Advice) => ("#fbe9e7", "#e57373"),
+        Some(ColumnKind::Fixed) => ("#e3f2fd", "#64b5f6"),
+        Some(ColumnKind::Instance) => ("#e8f5e9", "#81c784"),
+        None => ("#f3e5f5", "#ba68c8"),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::Value, pasta::Fp, plonk::Circuit};
+
+    use super::{Format, Layout};
+    use crate::{
+        circuits::{self, CircuitVisitor},
+        example4,
+    };
+
+    struct Text;
+
+    impl CircuitVisitor for Text {
+        type Output = String;
+
+        fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> String {
+            Layout::of(&circuit, &instances).unwrap().to_text()
+        }
+    }
+
+    #[test]
+    fn test_add() {
+        let text = circuits::visit_shape("add", Text).unwrap();
+        let expected = "\
+row  |  a0  a1  a2 |  i0 |  s0 | regions
+  0  |   =   =   = |   = |   1 | 0 'add region'
+  1  |   .   .   . |   = |   . |
+  2  |   .   .   . |   = |   . |
+
+# assigned  = copy endpoint  . empty  1 selector enabled
+
+copies
+  advice[0] row 0 = instance[0] row 0
+  advice[1] row 0 = instance[0] row 1
+  advice[2] row 0 = instance[0] row 2
+";
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn test_svg_and_html() {
+        let (a, b) = (Fp::from(2), Fp::from(3));
+        let circuit = example4::AddCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        let layout = Layout::of(&circuit, &[vec![a, b, a + b]]).unwrap();
+
+        // 5 columns and 3 rows, plus room for the row numbers and region names
+        let svg = layout.render(Format::Svg, "add");
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="278" height="86""#)
+        );
+        assert!(svg.contains(">a0</text>") && svg.contains(">s0</text>"));
+        assert!(svg.contains(">0 'add region'</text>"));
+        let res = format!(
+            "<title>advice[2] row 0 'res' = 0x{:064x}\ncopied to instance[0] row 2</title>",
+            5
+        );
+        assert!(svg.contains(&res), "{}", svg);
+        assert!(svg.contains("<title>s0 enabled at row 0</title>"));
+        assert_eq!(svg.matches("<circle").count(), 6);
+
+        let html = layout.render(Format::Html, "a < b");
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>a &lt; b</h1>"));
+        assert!(html.contains(&svg.replace("<title>add</title>", "<title>a &lt; b</title>")));
+
+        assert_eq!("svg".parse(), Ok(Format::Svg));
+        assert!("png".parse::<Format>().is_err());
+    }
+
+    // regenerate with `cargo run -- layout --circuit <name> --out testdata/layouts/<name>.txt`
+    // after a deliberate layout change
+    #[test]
+    fn test_snapshots() {
+        for info in circuits::CIRCUITS {
+            let path = format!(
+                "{}/testdata/layouts/{}.txt",
+                env!("CARGO_MANIFEST_DIR"),
+                info.name
+            );
+            let expected = std::fs::read_to_string(&path).unwrap();
+            assert_eq!(
+                circuits::visit_shape(info.name, Text).unwrap(),
+                expected,
+                "{}",
+                path
+            );
+        }
+    }
+}
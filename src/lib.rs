@@ -1,7 +1,34 @@
-mod example1;
-mod example2;
-mod test_fibonacci;
-mod example3;
-mod example4;
-mod example5;
-mod example4_gadget;
\ No newline at end of file
+#[cfg(test)]
+mod arbitrary;
+pub mod circuits;
+pub mod cost;
+pub mod diagnostics;
+pub mod envelope;
+pub mod example1;
+pub mod example2;
+pub mod example3;
+pub mod example4;
+pub mod example4_gadget;
+pub mod example5;
+pub mod field;
+pub mod formula;
+#[cfg(feature = "dot-graph")]
+pub mod graph;
+pub mod input;
+pub mod inspect;
+pub mod keys;
+pub mod layout;
+pub mod mutation;
+pub mod planner;
+pub mod poseidon;
+pub mod prover;
+pub mod recorder;
+pub mod server;
+pub mod service;
+pub mod sizing;
+#[cfg(test)]
+mod temp_dir;
+pub mod test_fibonacci;
+pub mod transcript;
+pub mod underconstrained;
+pub mod witness;
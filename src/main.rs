@@ -0,0 +1,608 @@
+use std::{error::Error, fs, path::PathBuf, process};
+
+use clap::{Parser, Subcommand};
+#[cfg(feature = "dot-graph")]
+use halo2_example::graph;
+use halo2_example::{
+    circuits::{self, CircuitVisitor, Inputs},
+    cost, diagnostics,
+    envelope::ProofEnvelope,
+    field::parse_fp,
+    formula::{self, ColumnNames},
+    input::{self, RegisteredInput},
+    inspect::CsInfo,
+    keys::{self, KeyCache},
+    layout::{Format, Layout},
+    prover,
+    server::Server,
+    sizing,
+    transcript::{TranscriptHash, TranscriptKind, TranscriptVisitor},
+    witness::{self, Witness},
+};
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_vk, Circuit, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+};
+
+type BoxError = Box<dyn Error>;
+
+#[derive(Parser)]
+#[command(
+    name = "halo2-example",
+    about = "setup, prove and verify the example circuits"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the available circuits and their inputs
+    List,
+    /// Write params and the verifying key of a circuit
+    Setup {
+        #[arg(long)]
+        circuit: String,
+        #[arg(long)]
+        k: Option<u32>,
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+    },
+    /// Prove a circuit for the given inputs and write a proof envelope
+    Prove {
+        #[arg(long)]
+        circuit: String,
+        #[command(flatten)]
+        inputs: InputArgs,
+        #[arg(long)]
+        k: Option<u32>,
+        /// params file written by `setup`, generated when missing
+        #[arg(long)]
+        params: Option<PathBuf>,
+        #[arg(long)]
+        out: PathBuf,
+        /// write the envelope as json instead of binary
+        #[arg(long)]
+        json: bool,
+        /// seed the prover's randomness so the same inputs give the same proof
+        #[arg(long)]
+        seed: Option<u64>,
+        /// blake2b, sha256 or poseidon, recorded in the envelope for the verifier
+        #[arg(long, default_value = "blake2b")]
+        transcript: TranscriptKind,
+    },
+    /// Verify a proof envelope
+    Verify {
+        #[arg(long)]
+        proof: PathBuf,
+        /// circuit the proof must be for, the envelope's circuit by default
+        #[arg(long)]
+        circuit: Option<String>,
+        /// k the proof must be for, the envelope's k by default
+        #[arg(long)]
+        k: Option<u32>,
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// verifying key file written by `setup`, checked against the circuit
+        #[arg(long)]
+        vk: Option<PathBuf>,
+    },
+    /// Print the size and cost of the circuits
+    Cost {
+        /// only this circuit, every registered circuit by default
+        #[arg(long)]
+        circuit: Option<String>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Serve list, vk fingerprint, prove and verify endpoints over http
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+        /// where params and verifying keys are cached
+        #[arg(long, default_value = "keys")]
+        keys_dir: PathBuf,
+        /// proving threads
+        #[arg(long, default_value_t = 4)]
+        workers: usize,
+    },
+    /// Run a circuit through MockProver
+    Mock {
+        #[arg(long)]
+        circuit: String,
+        #[command(flatten)]
+        inputs: InputArgs,
+        #[arg(long)]
+        k: Option<u32>,
+        /// print failures as json on stdout
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the rows, columns, selectors and copies a circuit lays out
+    Layout {
+        #[arg(long)]
+        circuit: String,
+        /// text, svg or html
+        #[arg(long, default_value = "text")]
+        format: Format,
+        /// write to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Export every cell a circuit assigns for the given inputs
+    Witness {
+        #[arg(long)]
+        circuit: String,
+        #[command(flatten)]
+        inputs: InputArgs,
+        /// csv or json
+        #[arg(long, default_value = "csv")]
+        format: witness::Format,
+        /// write to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Print every gate of a circuit as a formula with the degree of each constraint
+    Gates {
+        #[arg(long)]
+        circuit: String,
+    },
+    /// Print the gates, or the layouter namespaces, of a circuit as graphviz dot
+    #[cfg(feature = "dot-graph")]
+    Graph {
+        #[arg(long)]
+        circuit: String,
+        /// graph the namespaces the circuit opens instead of its gates
+        #[arg(long)]
+        namespaces: bool,
+        /// write to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(clap::Args)]
+struct InputArgs {
+    /// private input as name=value, value in decimal or 0x hex
+    #[arg(short, long = "input")]
+    input: Vec<String>,
+    /// json or toml file with the circuit's inputs and, optionally, its instances
+    #[arg(long)]
+    inputs: Option<PathBuf>,
+}
+
+impl InputArgs {
+    // `-i` values go on top of the file's
+    fn load(&self, circuit: &str) -> Result<RegisteredInput, BoxError> {
+        let mut loaded = match &self.inputs {
+            Some(path) => input::read_for(circuit, path)?,
+            None => RegisteredInput {
+                inputs: Inputs::new(),
+                instances: None,
+            },
+        };
+
+        for input in &self.input {
+            let (name, value) = input
+                .split_once('=')
+                .ok_or_else(|| format!("expected name=value, got {:?}", input))?;
+            loaded.inputs.insert(name.to_string(), parse_fp(value)?);
+        }
+
+        Ok(loaded)
+    }
+}
+
+fn default_k(circuit: &str, k: Option<u32>) -> Result<u32, BoxError> {
+    match k {
+        Some(k) => Ok(k),
+        None => circuits::visit_shape(circuit, MinK)?,
+    }
+}
+
+// the registered circuits take a handful of public values, so the rows they
+// assign decide k, not their instance columns
+struct MinK;
+
+impl CircuitVisitor for MinK {
+    type Output = Result<u32, BoxError>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+        Ok(sizing::min_k(&circuit, &instances)?)
+    }
+}
+
+fn load_params(path: Option<&PathBuf>, k: u32) -> Result<Params<EqAffine>, BoxError> {
+    let Some(path) = path else {
+        return Ok(Params::new(k));
+    };
+
+    let params = keys::read_params(path)?;
+    let found = keys::params_k(&params);
+    if found != k {
+        return Err(format!(
+            "params in {} are for k = {}, expected k = {}",
+            path.display(),
+            found,
+            k
+        )
+        .into());
+    }
+    Ok(params)
+}
+
+struct Setup<'a> {
+    params: &'a Params<EqAffine>,
+    vk_path: PathBuf,
+}
+
+impl CircuitVisitor for Setup<'_> {
+    type Output = Result<(), BoxError>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, _: Vec<Vec<Fp>>) -> Self::Output {
+        let vk = keygen_vk(self.params, &circuit)?;
+        keys::write_vk_fingerprint(&vk, &self.vk_path)?;
+        Ok(())
+    }
+}
+
+struct Prove<'a> {
+    name: &'a str,
+    k: u32,
+    params: &'a Params<EqAffine>,
+    seed: Option<u64>,
+    transcript: TranscriptKind,
+}
+
+impl CircuitVisitor for Prove<'_> {
+    type Output = Result<ProofEnvelope, BoxError>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+        // halo2 proves unsatisfied witnesses too, and nothing would ever verify them
+        let report = diagnostics::check(self.k, &circuit, instances.clone())?;
+        if !report.is_ok() {
+            eprint!("{}", report);
+            return Err(format!(
+                "{} constraint(s) not satisfied, no proof written",
+                report.failures.len()
+            )
+            .into());
+        }
+
+        let pk = prover::keygen(self.params, &circuit)?;
+
+        let proof = self.transcript.visit(ProveWith {
+            params: self.params,
+            pk: &pk,
+            circuit,
+            instances: &instances,
+            seed: self.seed,
+        })?;
+
+        Ok(ProofEnvelope::new(
+            self.name,
+            self.k,
+            self.transcript,
+            pk.get_vk(),
+            vec![instances],
+            proof,
+        ))
+    }
+}
+
+struct ProveWith<'a, C> {
+    params: &'a Params<EqAffine>,
+    pk: &'a ProvingKey<EqAffine>,
+    circuit: C,
+    instances: &'a [Vec<Fp>],
+    seed: Option<u64>,
+}
+
+impl<C: Circuit<Fp>> TranscriptVisitor for ProveWith<'_, C> {
+    type Output = Result<Vec<u8>, BoxError>;
+
+    fn visit<T: TranscriptHash>(self) -> Self::Output {
+        let columns: Vec<&[Fp]> = self.instances.iter().map(|column| &column[..]).collect();
+        let (params, pk, circuits) = (self.params, self.pk, &[self.circuit]);
+        let proof = match self.seed {
+            Some(seed) => prover::prove_seeded::<T, C>(params, pk, circuits, &[&columns], seed)?,
+            None => prover::prove_with::<T, C>(params, pk, circuits, &[&columns])?,
+        };
+        Ok(proof)
+    }
+}
+
+struct LoadVk<'a> {
+    params: &'a Params<EqAffine>,
+    vk_path: Option<&'a PathBuf>,
+}
+
+impl CircuitVisitor for LoadVk<'_> {
+    type Output = Result<VerifyingKey<EqAffine>, BoxError>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, _: Vec<Vec<Fp>>) -> Self::Output {
+        match self.vk_path {
+            Some(path) => Ok(keys::keygen_vk_checked(path, self.params, &circuit)?),
+            None => Ok(keygen_vk(self.params, &circuit)?),
+        }
+    }
+}
+
+struct Mock {
+    k: u32,
+    json: bool,
+}
+
+impl CircuitVisitor for Mock {
+    type Output = Result<(), BoxError>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+        let report = diagnostics::check(self.k, &circuit, instances)?;
+        if report.is_ok() {
+            return Ok(());
+        }
+
+        if self.json {
+            println!("{}", report.to_json());
+        } else {
+            eprint!("{}", report);
+        }
+        Err(format!("{} constraint(s) not satisfied", report.failures.len()).into())
+    }
+}
+
+struct Render<'a> {
+    name: &'a str,
+    format: Format,
+}
+
+impl CircuitVisitor for Render<'_> {
+    type Output = Result<String, BoxError>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+        let title = format!("{} layout", self.name);
+        Ok(Layout::of(&circuit, &instances)?.render(self.format, &title))
+    }
+}
+
+struct Export {
+    format: witness::Format,
+}
+
+impl CircuitVisitor for Export {
+    type Output = Result<String, BoxError>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+        Ok(Witness::of(&circuit, &instances)?.render(self.format))
+    }
+}
+
+struct Gates;
+
+impl CircuitVisitor for Gates {
+    type Output = String;
+
+    fn visit<C: Circuit<Fp>>(self, _: C, _: Vec<Vec<Fp>>) -> String {
+        formula::render(&CsInfo::of::<C>(), &ColumnNames::new())
+    }
+}
+
+#[cfg(feature = "dot-graph")]
+struct Dot {
+    namespaces: bool,
+}
+
+#[cfg(feature = "dot-graph")]
+impl CircuitVisitor for Dot {
+    type Output = String;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, _: Vec<Vec<Fp>>) -> String {
+        if self.namespaces {
+            graph::namespaces(&circuit)
+        } else {
+            graph::gates(&CsInfo::of::<C>())
+        }
+    }
+}
+
+fn write_or_print(out: Option<PathBuf>, text: &str) -> Result<(), BoxError> {
+    match out {
+        Some(out) => {
+            fs::write(&out, text)?;
+            println!("wrote {}", out.display());
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn read_envelope(path: &PathBuf) -> Result<ProofEnvelope, BoxError> {
+    let bytes = fs::read(path)?;
+    if bytes.starts_with(b"H2PF") {
+        Ok(ProofEnvelope::from_bytes(&bytes)?)
+    } else {
+        Ok(ProofEnvelope::from_json(std::str::from_utf8(&bytes)?)?)
+    }
+}
+
+fn run(cli: Cli) -> Result<(), BoxError> {
+    match cli.command {
+        Command::List => {
+            for info in circuits::CIRCUITS {
+                println!(
+                    "{:<18} k={:<3} inputs: {:<16} {}",
+                    info.name,
+                    default_k(info.name, None)?,
+                    info.inputs.join(","),
+                    info.description
+                );
+            }
+        }
+        Command::Setup {
+            circuit,
+            k,
+            out_dir,
+        } => {
+            let k = default_k(&circuit, k)?;
+            fs::create_dir_all(&out_dir)?;
+
+            let params = Params::new(k);
+            let params_path = out_dir.join(format!("params-k{}.bin", k));
+            keys::write_params(&params, &params_path)?;
+
+            let vk_path = out_dir.join(format!("{}-k{}.vk", circuit, k));
+            circuits::visit_shape(
+                &circuit,
+                Setup {
+                    params: &params,
+                    vk_path: vk_path.clone(),
+                },
+            )??;
+
+            println!("wrote {}", params_path.display());
+            println!("wrote {}", vk_path.display());
+        }
+        Command::Prove {
+            circuit,
+            inputs,
+            k,
+            params,
+            out,
+            json,
+            seed,
+            transcript,
+        } => {
+            let k = default_k(&circuit, k)?;
+            let params = load_params(params.as_ref(), k)?;
+            let input = inputs.load(&circuit)?;
+
+            let envelope = circuits::visit_with(
+                &circuit,
+                &input.inputs,
+                input.instances,
+                Prove {
+                    name: &circuit,
+                    k,
+                    params: &params,
+                    seed,
+                    transcript,
+                },
+            )??;
+
+            if json {
+                fs::write(&out, envelope.to_json())?;
+            } else {
+                fs::write(&out, envelope.to_bytes())?;
+            }
+            println!("wrote {}", out.display());
+        }
+        Command::Verify {
+            proof,
+            circuit,
+            k,
+            params,
+            vk,
+        } => {
+            let envelope = read_envelope(&proof)?;
+            // the proof still has to verify under the key of this circuit and k
+            let circuit = circuit.unwrap_or_else(|| envelope.circuit.clone());
+            let k = k.unwrap_or(envelope.k);
+            let params = load_params(params.as_ref(), k)?;
+
+            let vk = circuits::visit_shape(
+                &circuit,
+                LoadVk {
+                    params: &params,
+                    vk_path: vk.as_ref(),
+                },
+            )??;
+            envelope.verify(&circuit, k, &params, &vk)?;
+
+            println!("proof for {} is valid", circuit);
+        }
+        Command::Cost { circuit, json } => {
+            let mut reports = cost::report_all()?;
+            if let Some(circuit) = circuit {
+                reports.retain(|report| report.circuit == circuit);
+                if reports.is_empty() {
+                    return Err(circuits::CircuitError::UnknownCircuit(circuit).into());
+                }
+            }
+
+            if json {
+                println!("{}", cost::to_json(&reports));
+            } else {
+                print!("{}", cost::table(&reports));
+            }
+        }
+        Command::Serve {
+            addr,
+            keys_dir,
+            workers,
+        } => {
+            let server = Server::bind(&addr, KeyCache::new(keys_dir)?, workers)?;
+            println!("listening on http://{}", server.local_addr());
+            server.run();
+        }
+        Command::Mock {
+            circuit,
+            inputs,
+            k,
+            json,
+        } => {
+            let k = default_k(&circuit, k)?;
+            let input = inputs.load(&circuit)?;
+
+            circuits::visit_with(&circuit, &input.inputs, input.instances, Mock { k, json })??;
+            println!("{} is satisfied", circuit);
+        }
+        Command::Layout {
+            circuit,
+            format,
+            out,
+        } => {
+            let text = circuits::visit_shape(
+                &circuit,
+                Render {
+                    name: &circuit,
+                    format,
+                },
+            )??;
+            write_or_print(out, &text)?;
+        }
+        Command::Witness {
+            circuit,
+            inputs,
+            format,
+            out,
+        } => {
+            let input = inputs.load(&circuit)?;
+            let text =
+                circuits::visit_with(&circuit, &input.inputs, input.instances, Export { format })??;
+            write_or_print(out, &text)?;
+        }
+        Command::Gates { circuit } => {
+            print!("{}", circuits::visit_shape(&circuit, Gates)?);
+        }
+        #[cfg(feature = "dot-graph")]
+        Command::Graph {
+            circuit,
+            namespaces,
+            out,
+        } => {
+            let dot = circuits::visit_shape(&circuit, Dot { namespaces })?;
+            write_or_print(out, &format!("{}\n", dot))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run(Cli::parse()) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
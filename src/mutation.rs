@@ -0,0 +1,147 @@
+// negative tests: a satisfied circuit must stop being satisfied when any single
+// public input or private witness value changes
+use std::fmt;
+
+use halo2_proofs::{
+    pasta::Fp,
+    plonk::{Circuit, Error},
+};
+
+use crate::{
+    diagnostics::{self, Report},
+    sizing,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutation {
+    Instance { column: usize, row: usize },
+    // index into the witness values handed to the builder
+    Witness(usize),
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mutation::Instance { column, row } => write!(f, "instance[{}] row {}", column, row),
+            Mutation::Witness(i) => write!(f, "witness {}", i),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MutationError {
+    Plonk(Error),
+    // nothing to mutate from: the circuit rejects the witness and instances as given
+    Unsatisfied(Report),
+}
+
+impl fmt::Display for MutationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MutationError::Plonk(e) => write!(f, "{}", e),
+            MutationError::Unsatisfied(report) => {
+                write!(f, "unmutated circuit is not satisfied\n{}", report)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MutationError {}
+
+impl From<Error> for MutationError {
+    fn from(e: Error) -> Self {
+        MutationError::Plonk(e)
+    }
+}
+
+// every mutation MockProver still accepts. `build` makes the circuit from its
+// witness values, `instances` must be the valid public inputs for `witness`
+pub fn surviving_mutations<C: Circuit<Fp>>(
+    build: impl Fn(&[Fp]) -> C,
+    witness: &[Fp],
+    instances: &[Vec<Fp>],
+) -> Result<Vec<Mutation>, MutationError> {
+    let circuit = build(witness);
+    let k = sizing::min_k(&circuit, instances)?;
+    let accepts = |circuit: &C, instances: Vec<Vec<Fp>>| -> Result<bool, Error> {
+        Ok(diagnostics::check(k, circuit, instances)?.is_ok())
+    };
+
+    let report = diagnostics::check(k, &circuit, instances.to_vec())?;
+    if !report.is_ok() {
+        return Err(MutationError::Unsatisfied(report));
+    }
+
+    let mut survivors = vec![];
+    for (column, values) in instances.iter().enumerate() {
+        for row in 0..values.len() {
+            let mut mutated = instances.to_vec();
+            mutated[column][row] += Fp::one();
+            if accepts(&circuit, mutated)? {
+                survivors.push(Mutation::Instance { column, row });
+            }
+        }
+    }
+
+    for i in 0..witness.len() {
+        let mut mutated = witness.to_vec();
+        mutated[i] += Fp::one();
+        if accepts(&build(&mutated), instances.to_vec())? {
+            survivors.push(Mutation::Witness(i));
+        }
+    }
+
+    Ok(survivors)
+}
+
+pub fn assert_rejects_mutations<C: Circuit<Fp>>(
+    build: impl Fn(&[Fp]) -> C,
+    witness: &[Fp],
+    instances: &[Vec<Fp>],
+) {
+    let survivors = surviving_mutations(build, witness, instances).unwrap();
+    let survivors: Vec<String> = survivors.iter().map(|m| m.to_string()).collect();
+    assert!(
+        survivors.is_empty(),
+        "circuit still accepts mutated {}",
+        survivors.join(", ")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    use super::{surviving_mutations, Mutation, MutationError};
+    use crate::example3;
+
+    #[test]
+    fn test_survivor() {
+        // an extra instance row nothing is copied to can change freely
+        let x = Fp::from(3);
+        let survivors = surviving_mutations(
+            |w| example3::AddCircuit {
+                input: Value::known(w[0]),
+                output: Value::known(w[0]),
+            },
+            &[x],
+            &[vec![x, x, Fp::zero()]],
+        )
+        .unwrap();
+
+        assert_eq!(survivors, vec![Mutation::Instance { column: 0, row: 2 }]);
+    }
+
+    #[test]
+    fn test_unsatisfied() {
+        let res = surviving_mutations(
+            |w| example3::AddCircuit {
+                input: Value::known(w[0]),
+                output: Value::known(w[1]),
+            },
+            &[Fp::from(1), Fp::from(2)],
+            &[vec![Fp::from(1), Fp::from(2)]],
+        );
+        assert!(matches!(res, Err(MutationError::Unsatisfied(_))));
+    }
+}
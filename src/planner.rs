@@ -0,0 +1,71 @@
+// runs a circuit under another floor planner, to compare layouts
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Layouter,
+    plonk::{Circuit, ConstraintSystem, Error, FloorPlanner},
+};
+
+#[derive(Debug)]
+pub struct WithPlanner<C, P> {
+    pub circuit: C,
+    _planner: PhantomData<P>,
+}
+
+impl<C, P> WithPlanner<C, P> {
+    pub fn new(circuit: C) -> Self {
+        Self {
+            circuit,
+            _planner: PhantomData,
+        }
+    }
+}
+
+impl<F: Field, C: Circuit<F>, P: FloorPlanner> Circuit<F> for WithPlanner<C, P> {
+    type Config = C::Config;
+    type FloorPlanner = P;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.circuit.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.circuit.synthesize(config, layouter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::floor_planner::V1, dev::MockProver, pasta::Fp, plonk::Circuit};
+
+    use super::WithPlanner;
+    use crate::{
+        circuits::{self, CircuitVisitor},
+        sizing,
+    };
+
+    #[derive(Clone, Copy)]
+    struct UnderV1;
+
+    impl CircuitVisitor for UnderV1 {
+        type Output = ();
+
+        fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) {
+            let circuit = WithPlanner::<_, V1>::new(circuit);
+            let k = sizing::min_k(&circuit, &instances).unwrap();
+            MockProver::run(k, &circuit, instances)
+                .unwrap()
+                .assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_v1() {
+        circuits::tests::each_with_constant_inputs(Fp::from(2), UnderV1);
+    }
+}
@@ -0,0 +1,260 @@
+// Poseidon over the pasta Fp with the P128Pow5T3 parameters from halo2_gadgets
+// (width 3, rate 2, x^5 S-box, 8 full and 56 partial rounds), so hashes match
+// the Poseidon chip a recursive verifier would use
+use std::sync::OnceLock;
+
+use ff::{Field, PrimeField};
+use halo2_proofs::{arithmetic::FieldExt, pasta::Fp};
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+const FULL_ROUNDS: usize = 8;
+const PARTIAL_ROUNDS: usize = 56;
+
+type State = [Fp; WIDTH];
+type Mds = [[Fp; WIDTH]; WIDTH];
+
+struct Constants {
+    round_constants: Vec<State>,
+    mds: Mds,
+}
+
+// round constants and MDS matrix from the Grain LFSR, as specified in the Poseidon paper
+fn constants() -> &'static Constants {
+    static CONSTANTS: OnceLock<Constants> = OnceLock::new();
+    CONSTANTS.get_or_init(|| {
+        let mut grain = Grain::new();
+        let round_constants = (0..FULL_ROUNDS + PARTIAL_ROUNDS)
+            .map(|_| [(); WIDTH].map(|_| grain.next_field_element()))
+            .collect();
+
+        // cauchy matrix 1 / (x_i + y_j) over the first distinct xs and ys
+        let (xs, ys) = loop {
+            let vals: Vec<Fp> = (0..2 * WIDTH)
+                .map(|_| grain.next_field_element_without_rejection())
+                .collect();
+            let distinct = vals
+                .iter()
+                .enumerate()
+                .all(|(i, a)| vals[i + 1..].iter().all(|b| a != b));
+            if distinct {
+                break (vals[..WIDTH].to_vec(), vals[WIDTH..].to_vec());
+            }
+        };
+        let mut mds = [[Fp::zero(); WIDTH]; WIDTH];
+        for (i, row) in mds.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = (xs[i] + ys[j]).invert().unwrap();
+            }
+        }
+
+        Constants {
+            round_constants,
+            mds,
+        }
+    })
+}
+
+pub fn permute(state: &mut State) {
+    let Constants {
+        round_constants,
+        mds,
+    } = constants();
+    let half_full = FULL_ROUNDS / 2;
+
+    for (round, rcs) in round_constants.iter().enumerate() {
+        for (word, rc) in state.iter_mut().zip(rcs) {
+            *word += rc;
+        }
+
+        // partial rounds only apply the S-box to the first word
+        let partial = (half_full..half_full + PARTIAL_ROUNDS).contains(&round);
+        for word in state.iter_mut().take(if partial { 1 } else { WIDTH }) {
+            *word = word.pow_vartime([5]);
+        }
+
+        let mut next = [Fp::zero(); WIDTH];
+        for (i, word) in next.iter_mut().enumerate() {
+            for (j, value) in state.iter().enumerate() {
+                *word += mds[i][j] * value;
+            }
+        }
+        *state = next;
+    }
+}
+
+// hash of a fixed number of elements, the ConstantLength domain of halo2_gadgets
+pub fn hash<const L: usize>(message: [Fp; L]) -> Fp {
+    let mut sponge = Sponge::new(Fp::from_u128((L as u128) << 64));
+    for value in message {
+        sponge.absorb(value);
+    }
+    sponge.squeeze()
+}
+
+// duplex sponge: absorbs and squeezes can interleave, as a transcript needs
+#[derive(Debug, Clone)]
+pub struct Sponge {
+    state: State,
+    pending: Vec<Fp>,
+}
+
+impl Sponge {
+    pub fn new(capacity: Fp) -> Self {
+        let mut state = [Fp::zero(); WIDTH];
+        state[RATE] = capacity;
+        Self {
+            state,
+            pending: Vec::with_capacity(RATE),
+        }
+    }
+
+    pub fn absorb(&mut self, value: Fp) {
+        if self.pending.len() == RATE {
+            self.absorb_pending();
+        }
+        self.pending.push(value);
+    }
+
+    pub fn squeeze(&mut self) -> Fp {
+        self.absorb_pending();
+        self.state[0]
+    }
+
+    // messages are zero padded up to a multiple of the rate
+    fn absorb_pending(&mut self) {
+        for (word, value) in self.state.iter_mut().zip(self.pending.drain(..)) {
+            *word += value;
+        }
+        permute(&mut self.state);
+    }
+}
+
+// self-shrinking Grain LFSR seeded with the permutation parameters
+struct Grain {
+    state: Vec<bool>,
+    next_bit: usize,
+}
+
+impl Grain {
+    const STATE: usize = 80;
+
+    fn new() -> Self {
+        let mut state = vec![true; Self::STATE];
+        let mut set_bits = |offset: usize, len: usize, value: usize| {
+            for i in 0..len {
+                state[offset + len - 1 - i] = (value >> i) & 1 != 0;
+            }
+        };
+        // prime field, x^alpha S-box, field size, width, full and partial rounds
+        set_bits(0, 2, 1);
+        set_bits(2, 4, 0);
+        set_bits(6, 12, Fp::NUM_BITS as usize);
+        set_bits(18, 12, WIDTH);
+        set_bits(30, 10, FULL_ROUNDS);
+        set_bits(40, 10, PARTIAL_ROUNDS);
+
+        let mut grain = Grain {
+            state,
+            next_bit: Self::STATE,
+        };
+        // the first 160 bits are discarded
+        for _ in 0..20 {
+            grain.load_next_8_bits();
+            grain.next_bit = Self::STATE;
+        }
+        grain
+    }
+
+    fn load_next_8_bits(&mut self) {
+        let s = &self.state;
+        let new_bits: Vec<bool> = (0..8)
+            .map(|i| s[i + 62] ^ s[i + 51] ^ s[i + 38] ^ s[i + 23] ^ s[i + 13] ^ s[i])
+            .collect();
+        self.state.rotate_left(8);
+        self.next_bit -= 8;
+        self.state[self.next_bit..].copy_from_slice(&new_bits);
+    }
+
+    fn raw_bit(&mut self) -> bool {
+        if self.next_bit == Self::STATE {
+            self.load_next_8_bits();
+        }
+        let bit = self.state[self.next_bit];
+        self.next_bit += 1;
+        bit
+    }
+
+    // bits come in pairs, the second is kept only when the first is set
+    fn next_bit(&mut self) -> bool {
+        while !self.raw_bit() {
+            self.raw_bit();
+        }
+        self.raw_bit()
+    }
+
+    // NUM_BITS bits, most significant first, into little endian bytes
+    fn next_bytes<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        for i in (0..Fp::NUM_BITS as usize).rev() {
+            if self.next_bit() {
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+        }
+        bytes
+    }
+
+    fn next_field_element(&mut self) -> Fp {
+        loop {
+            if let Some(value) = Option::from(Fp::from_repr(self.next_bytes())) {
+                return value;
+            }
+        }
+    }
+
+    fn next_field_element_without_rejection(&mut self) -> Fp {
+        Fp::from_bytes_wide(&self.next_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::Fp;
+
+    use super::{hash, permute, Sponge};
+    use crate::field::parse_fp;
+
+    // zcash-test-vectors orchard_poseidon, as checked by halo2_gadgets
+    #[test]
+    fn test_vectors() {
+        let mut state = [Fp::from(0), Fp::from(1), Fp::from(2)];
+        permute(&mut state);
+        let expected = [
+            "0x2a526acd0b64b45394efb364f966240ff7e69a71d0b642a0aeb1bc024aeca456",
+            "0x13c5d1568b4aa43076ff7dae343d5512dcd42e7fbed9dafe012a3e9628e5b82a",
+            "0x0a49c868c6976544256fcd597984561af7cfdfe1bda42c7b359029a1d34e9ddd",
+        ]
+        .map(|hex| parse_fp(hex).unwrap());
+        assert_eq!(state, expected);
+
+        assert_eq!(
+            hash([Fp::from(0), Fp::from(1)]),
+            parse_fp("0x062ff1c32bb0ef109d6a1bc9399a083eed83c2a7fb54cdbe389d32a011d75883").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sponge() {
+        let mut a = Sponge::new(Fp::zero());
+        a.absorb(Fp::from(1));
+        let first = a.squeeze();
+        assert_ne!(a.squeeze(), first);
+
+        // absorbing after a squeeze changes what comes out next
+        let mut b = Sponge::new(Fp::zero());
+        b.absorb(Fp::from(1));
+        b.squeeze();
+        b.absorb(Fp::from(2));
+        assert_ne!(b.squeeze(), a.clone().squeeze());
+    }
+}
@@ -0,0 +1,404 @@
+// keygen, prove and verify for any circuit over the pasta curves,
+// lifted out of example3's test_real_prover
+use ff::Field;
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, BatchVerifier, Circuit, Error,
+        ProvingKey, SingleVerifier, VerificationStrategy, VerifyingKey,
+    },
+    poly::commitment::{Guard, Params, MSM},
+    transcript::EncodedChallenge,
+};
+use rand_chacha::ChaCha20Rng;
+use rand_core::{OsRng, RngCore, SeedableRng};
+
+use crate::{
+    sizing,
+    transcript::{Blake2b, TranscriptHash, TranscriptKind},
+};
+
+pub fn setup<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+) -> Result<(Params<EqAffine>, ProvingKey<EqAffine>), Error> {
+    let params: Params<EqAffine> = Params::new(k);
+    let pk = keygen(&params, circuit)?;
+
+    Ok((params, pk))
+}
+
+// setup at the smallest k that fits `circuit` with instance columns shaped like `instances`
+pub fn setup_min_k<C: Circuit<Fp>>(
+    circuit: &C,
+    instances: &[Vec<Fp>],
+) -> Result<(u32, Params<EqAffine>, ProvingKey<EqAffine>), Error> {
+    let k = sizing::min_k(circuit, instances)?;
+    let (params, pk) = setup(k, circuit)?;
+
+    Ok((k, params, pk))
+}
+
+pub fn keygen<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    circuit: &C,
+) -> Result<ProvingKey<EqAffine>, Error> {
+    // keys only depend on the circuit shape, never on the witness
+    let empty_circuit = circuit.without_witnesses();
+    let vk = keygen_vk(params, &empty_circuit)?;
+
+    keygen_pk(params, vk, &empty_circuit)
+}
+
+// one proof for all `circuits`, `instances[i]` holds the instance columns of `circuits[i]`
+pub fn prove<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuits: &[C],
+    instances: &[&[&[Fp]]],
+) -> Result<Vec<u8>, Error> {
+    prove_with::<Blake2b, C>(params, pk, circuits, instances)
+}
+
+pub fn verify(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    instances: &[&[&[Fp]]],
+    proof: &[u8],
+) -> Result<(), Error> {
+    verify_with::<Blake2b>(params, vk, instances, proof)
+}
+
+// `prove` with the transcript hashed by `T`, verify with the same `T`
+pub fn prove_with<T: TranscriptHash, C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuits: &[C],
+    instances: &[&[&[Fp]]],
+) -> Result<Vec<u8>, Error> {
+    prove_with_rng::<T, C>(params, pk, circuits, instances, OsRng)
+}
+
+// the same proof bytes for the same seed, key and witness; for golden files and
+// reproducing verification failures, never for proofs meant to hide the witness
+pub fn prove_seeded<T: TranscriptHash, C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuits: &[C],
+    instances: &[&[&[Fp]]],
+    seed: u64,
+) -> Result<Vec<u8>, Error> {
+    let rng = ChaCha20Rng::seed_from_u64(seed);
+    prove_with_rng::<T, C>(params, pk, circuits, instances, rng)
+}
+
+pub fn prove_with_rng<T: TranscriptHash, C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuits: &[C],
+    instances: &[&[&[Fp]]],
+    rng: impl RngCore,
+) -> Result<Vec<u8>, Error> {
+    let mut transcript = T::writer();
+    create_proof(params, pk, circuits, instances, rng, &mut transcript)?;
+
+    Ok(T::finalize(transcript))
+}
+
+pub fn verify_with<T: TranscriptHash>(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    instances: &[&[&[Fp]]],
+    proof: &[u8],
+) -> Result<(), Error> {
+    let strategy = SingleVerifier::new(params);
+    let mut transcript = T::reader(proof);
+
+    verify_proof(params, vk, strategy, instances, &mut transcript)
+}
+
+// owned instances of one proof: per circuit copy, per instance column
+pub type Instances = Vec<Vec<Vec<Fp>>>;
+
+// borrows owned instances in the nested slice shape create_proof and verify_proof take
+pub fn with_instance_slices<R>(instances: &[Vec<Vec<Fp>>], f: impl FnOnce(&[&[&[Fp]]]) -> R) -> R {
+    let columns: Vec<Vec<&[Fp]>> = instances
+        .iter()
+        .map(|columns| columns.iter().map(|column| &column[..]).collect())
+        .collect();
+    let instances: Vec<&[&[Fp]]> = columns.iter().map(|columns| &columns[..]).collect();
+
+    f(&instances)
+}
+
+// one proof over independent witnesses of the same circuit, `instances[i]` belongs to `circuits[i]`
+pub fn prove_multi<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuits: &[C],
+    instances: &[Vec<Vec<Fp>>],
+) -> Result<Vec<u8>, Error> {
+    if circuits.len() != instances.len() {
+        return Err(Error::InvalidInstances);
+    }
+
+    with_instance_slices(instances, |instances| {
+        prove(params, pk, circuits, instances)
+    })
+}
+
+pub fn verify_multi(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    instances: &[Vec<Vec<Fp>>],
+    proof: &[u8],
+) -> Result<(), Error> {
+    with_instance_slices(instances, |instances| verify(params, vk, instances, proof))
+}
+
+#[derive(Debug)]
+pub struct BatchFailure {
+    // index into the batch and the error of every proof that failed on its own
+    pub failed: Vec<(usize, Error)>,
+}
+
+// halo2's BatchVerifier only reads Blake2b transcripts, so batches of other
+// transcripts are accumulated here: the MSM each proof leaves to check, scaled by a
+// random factor, summed into one. Unlike BatchVerifier this reads the proofs one
+// after another
+struct BatchStrategy<'params> {
+    msm: MSM<'params, EqAffine>,
+}
+
+impl<'params> VerificationStrategy<'params, EqAffine> for BatchStrategy<'params> {
+    type Output = MSM<'params, EqAffine>;
+
+    fn process<E: EncodedChallenge<EqAffine>>(
+        self,
+        f: impl FnOnce(MSM<'params, EqAffine>) -> Result<Guard<'params, EqAffine, E>, Error>,
+    ) -> Result<Self::Output, Error> {
+        Ok(f(self.msm)?.use_challenges())
+    }
+}
+
+fn accumulate<'params, T: TranscriptHash>(
+    params: &'params Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proofs: &[(Instances, Vec<u8>)],
+) -> Result<MSM<'params, EqAffine>, Error> {
+    proofs
+        .iter()
+        .try_fold(params.empty_msm(), |mut acc, (instances, proof)| {
+            let msm = with_instance_slices(instances, |instances| {
+                let strategy = BatchStrategy {
+                    msm: params.empty_msm(),
+                };
+                verify_proof(params, vk, strategy, instances, &mut T::reader(proof))
+            })?;
+            acc.scale(Fp::random(OsRng));
+            acc.add_msm(&msm);
+            Ok(acc)
+        })
+}
+
+// verifies many proofs for the same key with one multiexp, on failure every proof
+// is checked on its own to find the bad ones
+pub fn verify_batch<T: TranscriptHash>(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proofs: &[(Instances, Vec<u8>)],
+) -> Result<(), BatchFailure> {
+    let valid = match T::KIND {
+        TranscriptKind::Blake2b => {
+            let mut batch = BatchVerifier::new();
+            for (instances, proof) in proofs {
+                batch.add_proof(instances.clone(), proof.clone());
+            }
+            batch.finalize(params, vk)
+        }
+        TranscriptKind::Sha256 | TranscriptKind::Poseidon => {
+            accumulate::<T>(params, vk, proofs).is_ok_and(|msm| msm.eval())
+        }
+    };
+    if valid {
+        return Ok(());
+    }
+
+    let failed = proofs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (instances, proof))| {
+            with_instance_slices(instances, |instances| {
+                verify_with::<T>(params, vk, instances, proof)
+            })
+            .err()
+            .map(|e| (i, e))
+        })
+        .collect();
+
+    Err(BatchFailure { failed })
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::Value,
+        pasta::{EqAffine, Fp},
+        plonk::{Error, ProvingKey},
+        poly::commitment::Params,
+    };
+
+    use super::{
+        prove_multi, prove_seeded, prove_with, setup_min_k, verify, verify_batch, verify_multi,
+        verify_with, Instances,
+    };
+    use crate::{
+        example4::AddCircuit,
+        transcript::{Blake2b, Poseidon, Sha256, TranscriptHash},
+    };
+
+    // n add circuits with distinct witnesses and their instances
+    fn add_circuits(n: u64) -> (Vec<AddCircuit<Fp>>, Instances) {
+        (0..n)
+            .map(|i| {
+                let (a, b) = (Fp::from(i), Fp::from(2 * i + 1));
+                let circuit = AddCircuit {
+                    a: Value::known(a),
+                    b: Value::known(b),
+                };
+                (circuit, vec![vec![a, b, a + b]])
+            })
+            .unzip()
+    }
+
+    fn add_setup() -> (Params<EqAffine>, ProvingKey<EqAffine>) {
+        let (circuits, instances) = add_circuits(1);
+        let (_, params, pk) = setup_min_k(&circuits[0], &instances[0]).unwrap();
+        (params, pk)
+    }
+
+    #[test]
+    fn test_multi() {
+        let (params, pk) = add_setup();
+
+        for n in [1, 2, 16] {
+            let (circuits, instances) = add_circuits(n);
+            let proof = prove_multi(&params, &pk, &circuits, &instances).unwrap();
+            assert!(verify_multi(&params, pk.get_vk(), &instances, &proof).is_ok());
+
+            // the same proof does not hold for the copies in another order
+            if n > 1 {
+                let mut swapped = instances.clone();
+                swapped.swap(0, 1);
+                assert!(verify_multi(&params, pk.get_vk(), &swapped, &proof).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_instance_count() {
+        let (params, pk) = add_setup();
+
+        let (circuits, instances) = add_circuits(2);
+        let res = prove_multi(&params, &pk, &circuits, &instances[..1]);
+        assert!(matches!(res, Err(Error::InvalidInstances)));
+    }
+
+    #[test]
+    fn test_multi_proof_size() {
+        let (params, pk) = add_setup();
+        let (circuits, instances) = add_circuits(16);
+
+        let multi = prove_multi(&params, &pk, &circuits, &instances).unwrap();
+        let single = prove_multi(&params, &pk, &circuits[..1], &instances[..1]).unwrap();
+
+        // the vanishing argument and the opening proof are shared across copies
+        assert!(multi.len() < 16 * single.len());
+    }
+
+    fn batch<T: TranscriptHash>() {
+        let (params, pk) = add_setup();
+
+        let mut proofs: Vec<(Instances, Vec<u8>)> = (0..4u64)
+            .map(|i| {
+                let (a, b) = (Fp::from(i), Fp::from(i + 1));
+                let circuit = AddCircuit {
+                    a: Value::known(a),
+                    b: Value::known(b),
+                };
+                let public_input = vec![a, b, a + b];
+                let proof =
+                    prove_with::<T, _>(&params, &pk, &[circuit], &[&[&public_input]]).unwrap();
+                (vec![vec![public_input]], proof)
+            })
+            .collect();
+        assert!(verify_batch::<T>(&params, pk.get_vk(), &proofs).is_ok());
+
+        // claim a wrong sum for the third proof
+        proofs[2].0[0][0][2] += Fp::one();
+        let failure = verify_batch::<T>(&params, pk.get_vk(), &proofs).unwrap_err();
+        let failed: Vec<usize> = failure.failed.iter().map(|(i, _)| *i).collect();
+        assert_eq!(failed, vec![2]);
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        batch::<Blake2b>();
+        batch::<Poseidon>();
+    }
+
+    #[test]
+    fn test_verify_batch_other_transcript() {
+        let (params, pk) = add_setup();
+        let (circuits, instances) = add_circuits(1);
+
+        let proof = prove_multi(&params, &pk, &circuits, &instances).unwrap();
+        let proofs = vec![(instances, proof)];
+        assert!(verify_batch::<Blake2b>(&params, pk.get_vk(), &proofs).is_ok());
+        assert!(verify_batch::<Sha256>(&params, pk.get_vk(), &proofs).is_err());
+    }
+
+    fn prove_and_verify<T: TranscriptHash>() -> Vec<u8> {
+        let (params, pk) = add_setup();
+        let (circuits, instances) = add_circuits(1);
+        let instances: &[&[Fp]] = &[&instances[0][0]];
+
+        let proof = prove_with::<T, _>(&params, &pk, &circuits, &[instances]).unwrap();
+        assert!(verify_with::<T>(&params, pk.get_vk(), &[instances], &proof).is_ok());
+        proof
+    }
+
+    #[test]
+    fn test_transcripts() {
+        let (params, pk) = add_setup();
+        let (_, instances) = add_circuits(1);
+        let instances: &[&[Fp]] = &[&instances[0][0]];
+
+        prove_and_verify::<Blake2b>();
+        prove_and_verify::<Poseidon>();
+        let proof = prove_and_verify::<Sha256>();
+
+        // challenges differ, so a proof only verifies under its own hash
+        assert!(verify_with::<Blake2b>(&params, pk.get_vk(), &[instances], &proof).is_err());
+    }
+
+    #[test]
+    fn test_seeded() {
+        let (params, pk) = add_setup();
+        let (circuits, instances) = add_circuits(1);
+        let instances: &[&[Fp]] = &[&instances[0][0]];
+
+        let proof = prove_seeded::<Blake2b, _>(&params, &pk, &circuits, &[instances], 1).unwrap();
+        assert!(verify(&params, pk.get_vk(), &[instances], &proof).is_ok());
+
+        let again = prove_seeded::<Blake2b, _>(&params, &pk, &circuits, &[instances], 1).unwrap();
+        assert_eq!(proof, again);
+
+        let other = prove_seeded::<Blake2b, _>(&params, &pk, &circuits, &[instances], 2).unwrap();
+        assert_ne!(proof, other);
+
+        let poseidon =
+            prove_seeded::<Poseidon, _>(&params, &pk, &circuits, &[instances], 1).unwrap();
+        assert!(verify_with::<Poseidon>(&params, pk.get_vk(), &[instances], &poseidon).is_ok());
+    }
+}
@@ -0,0 +1,294 @@
+// lays a circuit out against an `Assignment` that writes everything down: the
+// regions, every assigned cell with its name and value, the enabled selectors and
+// the copy constraints
+use std::fmt;
+
+use halo2_proofs::{
+    circuit::Value,
+    pasta::Fp,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::inspect::{self, ColumnKind, CsInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Position {
+    pub kind: ColumnKind,
+    pub column: usize,
+    pub row: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}] row {}", self.kind, self.column, self.row)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionRecord {
+    pub name: String,
+    // first and last row, None for regions that assign nothing
+    pub rows: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellRecord {
+    pub position: Position,
+    // index into `Recording::regions`, None for cells assigned outside any region
+    pub region: Option<usize>,
+    pub name: String,
+    // None when the circuit was laid out without witnesses
+    pub value: Option<Fp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorRecord {
+    pub selector: usize,
+    pub row: usize,
+    pub region: Option<usize>,
+    pub name: String,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Recording {
+    pub regions: Vec<RegionRecord>,
+    // advice and fixed cells in the order they were assigned
+    pub cells: Vec<CellRecord>,
+    pub selectors: Vec<SelectorRecord>,
+    pub copies: Vec<(Position, Position)>,
+    pub instances: Vec<Vec<Fp>>,
+    region: Option<usize>,
+}
+
+impl Recording {
+    // one past the highest row assigned, copied into or with a selector enabled
+    pub fn rows(&self) -> usize {
+        let cells = self.cells.iter().map(|c| c.position.row);
+        let selectors = self.selectors.iter().map(|s| s.row);
+        let copies = self.copies.iter().flat_map(|(l, r)| [l.row, r.row]);
+        cells
+            .chain(selectors)
+            .chain(copies)
+            .map(|row| row + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn cell(&self, position: Position) -> Option<&CellRecord> {
+        self.cells.iter().rev().find(|c| c.position == position)
+    }
+
+    // a region spans the rows it assigns or enables selectors on, as in MockProver
+    fn touch_region(&mut self, row: usize) -> Option<usize> {
+        let index = self.region?;
+        let (start, end) = self.regions[index].rows.get_or_insert((row, row));
+        *start = (*start).min(row);
+        *end = (*end).max(row);
+        Some(index)
+    }
+
+    fn assign<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Any>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fp>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let mut value = None;
+        to().map(|v| value = Some(v.into().evaluate()));
+
+        let (kind, column) = inspect::column_id(column);
+        let region = self.touch_region(row);
+        self.cells.push(CellRecord {
+            position: Position { kind, column, row },
+            region,
+            name: annotation().into(),
+            value,
+        });
+        Ok(())
+    }
+}
+
+impl Assignment<Fp> for Recording {
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.region = Some(self.regions.len());
+        self.regions.push(RegionRecord {
+            name: name().into(),
+            rows: None,
+        });
+    }
+
+    fn exit_region(&mut self) {
+        self.region = None;
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // Selector(index, simple)
+        let text = format!("{:?}", selector);
+        let node = inspect::parse_debug(&text).expect("selector is valid debug output");
+        let region = self.touch_region(row);
+        self.selectors.push(SelectorRecord {
+            selector: node.items()[0].as_usize(),
+            row,
+            region,
+            name: annotation().into(),
+        });
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<Fp>, Error> {
+        let (_, index) = inspect::column_id(column);
+        Ok(self
+            .instances
+            .get(index)
+            .and_then(|values| values.get(row))
+            .map_or(Value::unknown(), |v| Value::known(*v)))
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fp>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.assign(annotation, column.into(), row, to)
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fp>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.assign(annotation, column.into(), row, to)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        let position = |column, row| {
+            let (kind, column) = inspect::column_id(column);
+            Position { kind, column, row }
+        };
+        self.copies.push((
+            position(left_column, left_row),
+            position(right_column, right_row),
+        ));
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<Fp>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+// lays `circuit` out once, answering instance queries from `instances`
+pub fn record<C: Circuit<Fp>>(circuit: &C, instances: &[Vec<Fp>]) -> Result<Recording, Error> {
+    let mut cs = ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+    let constants = CsInfo::from_cs(&cs).constant_columns();
+
+    let mut recording = Recording {
+        instances: instances.to_vec(),
+        ..Recording::default()
+    };
+    C::FloorPlanner::synthesize(&mut recording, circuit, config, constants)?;
+    Ok(recording)
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    use super::{record, Position};
+    use crate::{example4, inspect::ColumnKind};
+
+    #[test]
+    fn test_record() {
+        let (a, b) = (Fp::from(2), Fp::from(3));
+        let circuit = example4::AddCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        let recording = record(&circuit, &[vec![a, b, a + b]]).unwrap();
+
+        assert_eq!(recording.regions.len(), 1);
+        assert_eq!(recording.regions[0].name, "add region");
+        assert_eq!(recording.regions[0].rows, Some((0, 0)));
+        // the copies reach instance rows 1 and 2
+        assert_eq!(recording.rows(), 3);
+        assert_eq!(recording.selectors.len(), 1);
+
+        let res = recording
+            .cell(Position {
+                kind: ColumnKind::Advice,
+                column: 2,
+                row: 0,
+            })
+            .unwrap();
+        assert_eq!(res.region, Some(0));
+        assert_eq!(res.value, Some(a + b));
+
+        // every advice cell is copied to or from the instance column
+        assert_eq!(recording.copies.len(), 3);
+        assert!(recording
+            .copies
+            .iter()
+            .all(|(_, r)| r.kind == ColumnKind::Instance));
+    }
+}
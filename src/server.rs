@@ -0,0 +1,267 @@
+// local http server over the circuit registry, for clients that do not link rust
+//
+//   GET  /circuits           registered circuits and their inputs
+//   GET  /circuits/<name>/fingerprint
+//                            the vk fingerprint file `setup` writes: "H2VK" and
+//                            a hash of the vk. halo2_proofs 0.2 cannot serialize
+//                            a verifying key, so this identifies the key but
+//                            cannot verify anything on its own
+//   POST /prove              {"circuit", "inputs", "instances"?, "transcript"?}
+//                            -> proof envelope json
+//   POST /verify             proof envelope json -> {"valid", "error"?}
+//
+// errors come back as {"error": message} with a 4xx or 5xx status, bodies
+// over MAX_BODY bytes are refused with 413
+use std::{
+    io::{self, Read},
+    net::SocketAddr,
+    thread,
+};
+
+use halo2_proofs::{pasta::Fp, plonk::Circuit};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::{
+    circuits::{self, CircuitError, CircuitVisitor, CIRCUITS},
+    envelope::ProofEnvelope,
+    input::{self, InputError, InputFormat},
+    keys::{self, KeyCache, SharedKeys},
+    service::{Job, JobError, ProvingService},
+    sizing,
+    transcript::TranscriptKind,
+};
+
+// far above any envelope of the registered circuits
+pub const MAX_BODY: u64 = 1 << 20;
+
+// requests served at once, later ones wait for a free handler
+pub const HANDLERS: usize = 8;
+
+pub struct Server {
+    http: tiny_http::Server,
+    service: ProvingService,
+}
+
+struct HttpError {
+    status: u16,
+    message: String,
+}
+
+enum Body {
+    Json(String),
+    Bytes(Vec<u8>),
+}
+
+impl HttpError {
+    fn new(status: u16, message: impl ToString) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<CircuitError> for HttpError {
+    fn from(e: CircuitError) -> Self {
+        match e {
+            CircuitError::UnknownCircuit(_) => HttpError::new(404, e),
+            _ => HttpError::new(400, e),
+        }
+    }
+}
+
+impl From<InputError> for HttpError {
+    fn from(e: InputError) -> Self {
+        match e {
+            InputError::Circuit(e) => e.into(),
+            _ => HttpError::new(400, e),
+        }
+    }
+}
+
+impl From<JobError> for HttpError {
+    fn from(e: JobError) -> Self {
+        match e {
+            JobError::Circuit(e) => e.into(),
+            JobError::InstanceMismatch { .. } | JobError::Unsatisfied(_) => HttpError::new(400, e),
+            _ => HttpError::new(500, e),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ProveRequest {
+    circuit: String,
+    inputs: serde_json::Map<String, Value>,
+    // implied by the inputs when left out
+    #[serde(default)]
+    instances: Option<Value>,
+    // blake2b, sha256 or poseidon, blake2b when left out
+    #[serde(default)]
+    transcript: TranscriptKind,
+}
+
+impl Server {
+    pub fn bind(addr: &str, cache: KeyCache, workers: usize) -> io::Result<Self> {
+        let http = tiny_http::Server::http(addr).map_err(io::Error::other)?;
+
+        Ok(Self {
+            http,
+            service: ProvingService::new(cache, workers),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.http
+            .server_addr()
+            .to_ip()
+            .expect("bound to a tcp address")
+    }
+
+    // serves until `stop` on HANDLERS threads
+    pub fn run(&self) {
+        thread::scope(|scope| {
+            for _ in 0..HANDLERS {
+                scope.spawn(|| {
+                    for request in self.http.incoming_requests() {
+                        self.respond(request);
+                    }
+                });
+            }
+        });
+    }
+
+    pub fn stop(&self) {
+        // each unblock wakes one handler
+        for _ in 0..HANDLERS {
+            self.http.unblock();
+        }
+    }
+
+    fn respond(&self, mut request: Request) {
+        let result = read_body(&mut request)
+            .and_then(|body| self.handle(request.method(), request.url(), &body));
+
+        let (status, body) = match result {
+            Ok(body) => (200, body),
+            Err(e) => (
+                e.status,
+                Body::Json(json!({ "error": e.message }).to_string()),
+            ),
+        };
+        let (content_type, body) = match body {
+            Body::Json(text) => ("application/json", text.into_bytes()),
+            Body::Bytes(bytes) => ("application/octet-stream", bytes),
+        };
+        let content_type = Header::from_bytes("Content-Type", content_type).unwrap();
+        let response = Response::from_data(body)
+            .with_status_code(status)
+            .with_header(content_type);
+        // the client may have gone away, nothing to do about it
+        let _ = request.respond(response);
+    }
+
+    fn handle(&self, method: &Method, url: &str, body: &str) -> Result<Body, HttpError> {
+        let path: Vec<&str> = url.trim_matches('/').split('/').collect();
+
+        match (method, path.as_slice()) {
+            (Method::Get, ["circuits"]) => Ok(Body::Json(list())),
+            (Method::Get, ["circuits", name, "fingerprint"]) => {
+                let (_, (_, pk)) = circuits::visit_shape(name, Keys(&self.service))??;
+                Ok(Body::Bytes(keys::vk_fingerprint_bytes(pk.get_vk())))
+            }
+            (Method::Post, ["prove"]) => self.prove(body).map(Body::Json),
+            (Method::Post, ["verify"]) => self.verify(body).map(Body::Json),
+            (_, ["circuits"] | ["circuits", _, "fingerprint"] | ["prove"] | ["verify"]) => Err(
+                HttpError::new(405, format!("{} not allowed on {}", method, url)),
+            ),
+            _ => Err(HttpError::new(404, format!("no route {}", url))),
+        }
+    }
+
+    fn prove(&self, body: &str) -> Result<String, HttpError> {
+        let request: ProveRequest =
+            serde_json::from_str(body).map_err(|e| HttpError::new(400, e))?;
+
+        // the same shape as an input file, checked by the circuit's own input type
+        let mut fields = request.inputs;
+        if let Some(instances) = request.instances {
+            fields.insert("instances".to_string(), instances);
+        }
+        let given = input::parse_for(
+            &request.circuit,
+            &Value::Object(fields).to_string(),
+            InputFormat::Json,
+        )?;
+        let instances = match given.instances {
+            Some(instances) => instances,
+            None => circuits::instances(&request.circuit, &given.inputs)?,
+        };
+
+        let job = Job {
+            circuit: request.circuit,
+            inputs: given.inputs,
+            instances,
+            transcript: request.transcript,
+        };
+        Ok(self.service.submit(job).wait()?.to_json())
+    }
+
+    fn verify(&self, body: &str) -> Result<String, HttpError> {
+        let envelope = ProofEnvelope::from_json(body).map_err(|e| HttpError::new(400, e))?;
+        let (k, (params, pk)) = circuits::visit_shape(&envelope.circuit, Keys(&self.service))??;
+
+        let res = envelope.verify(&envelope.circuit, k, &params, pk.get_vk());
+        Ok(json!({
+            "valid": res.is_ok(),
+            "error": res.err().map(|e| e.to_string()),
+        })
+        .to_string())
+    }
+}
+
+// reads one byte past the limit to tell a body of exactly MAX_BODY from a longer one
+fn read_body(request: &mut Request) -> Result<String, HttpError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| HttpError::new(400, e))?;
+
+    if body.len() as u64 > MAX_BODY {
+        return Err(HttpError::new(
+            413,
+            format!("request body over {} bytes", MAX_BODY),
+        ));
+    }
+    Ok(body)
+}
+
+fn list() -> String {
+    let circuits: Vec<Value> = CIRCUITS
+        .iter()
+        .map(|info| {
+            json!({
+                "name": info.name,
+                "description": info.description,
+                "inputs": info.inputs,
+            })
+        })
+        .collect();
+    Value::from(circuits).to_string()
+}
+
+// k and keys of a registered circuit, from the same cache the workers prove with
+struct Keys<'a>(&'a ProvingService);
+
+impl CircuitVisitor for Keys<'_> {
+    type Output = Result<(u32, SharedKeys), JobError>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+        let k = sizing::min_k(&circuit, &instances)?;
+        Ok((k, self.0.keys(k, &circuit)?))
+    }
+}
@@ -0,0 +1,468 @@
+// proving jobs for registered circuits, run by a pool of worker threads that
+// share one KeyCache, so keygen runs once per circuit and k
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex, PoisonError,
+    },
+    thread,
+};
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{Circuit, Error, ProvingKey},
+    poly::commitment::Params,
+};
+
+use crate::{
+    circuits::{self, CircuitError, CircuitVisitor, Inputs},
+    diagnostics::{self, Report},
+    envelope::ProofEnvelope,
+    keys::{self, KeyCache, KeyError, SharedKeys},
+    prover, sizing,
+    transcript::{TranscriptHash, TranscriptKind, TranscriptVisitor},
+};
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub circuit: String,
+    pub inputs: Inputs,
+    // one vec per instance column, must match what the inputs imply
+    pub instances: Vec<Vec<Fp>>,
+    pub transcript: TranscriptKind,
+}
+
+#[derive(Debug)]
+pub enum JobError {
+    Circuit(CircuitError),
+    Key(KeyError),
+    InstanceMismatch {
+        expected: Vec<Vec<Fp>>,
+        found: Vec<Vec<Fp>>,
+    },
+    // the witness fails the circuit's constraints, so no proof of it could verify
+    Unsatisfied(Report),
+    Prove(Error),
+    // the job panicked, or an earlier panic left the key cache poisoned
+    Panicked(String),
+    // the pool shut down before answering
+    Cancelled,
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobError::Circuit(e) => write!(f, "{}", e),
+            JobError::Key(e) => write!(f, "{}", e),
+            JobError::InstanceMismatch { .. } => {
+                write!(f, "instances do not match the ones implied by the inputs")
+            }
+            JobError::Unsatisfied(report) => {
+                write!(f, "the inputs do not satisfy the circuit, {}", report)
+            }
+            JobError::Prove(e) => write!(f, "proving failed: {}", e),
+            JobError::Panicked(message) => write!(f, "proving job panicked: {}", message),
+            JobError::Cancelled => write!(f, "proving job was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for JobError {}
+
+impl From<CircuitError> for JobError {
+    fn from(e: CircuitError) -> Self {
+        JobError::Circuit(e)
+    }
+}
+
+impl From<KeyError> for JobError {
+    fn from(e: KeyError) -> Self {
+        JobError::Key(e)
+    }
+}
+
+impl From<Error> for JobError {
+    fn from(e: Error) -> Self {
+        JobError::Prove(e)
+    }
+}
+
+impl<T> From<PoisonError<T>> for JobError {
+    fn from(_: PoisonError<T>) -> Self {
+        JobError::Panicked("key cache poisoned by an earlier panic".to_string())
+    }
+}
+
+impl JobError {
+    fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "unknown panic".to_string(),
+            },
+        };
+        JobError::Panicked(message)
+    }
+}
+
+pub type JobResult = Result<ProofEnvelope, JobError>;
+
+type Task = (Job, mpsc::Sender<JobResult>);
+
+pub struct JobHandle(mpsc::Receiver<JobResult>);
+
+impl JobHandle {
+    pub fn wait(self) -> JobResult {
+        self.0.recv().unwrap_or(Err(JobError::Cancelled))
+    }
+}
+
+// keys the workers share. The cache lock only guards params and the fingerprint
+// files; keygen runs under the lock of its own (circuit, k) slot, so jobs for other
+// circuits go on while a key is generated and the same key is never generated twice
+struct SharedCache {
+    cache: Mutex<KeyCache>,
+    slots: Mutex<HashMap<(String, u32), Arc<Slot>>>,
+    // keygen runs so far
+    keygens: AtomicUsize,
+}
+
+// empty until keygen for its circuit and k succeeds
+type Slot = Mutex<Option<SharedKeys>>;
+
+impl SharedCache {
+    fn new(cache: KeyCache) -> Self {
+        Self {
+            cache: Mutex::new(cache),
+            slots: Mutex::new(HashMap::new()),
+            keygens: AtomicUsize::new(0),
+        }
+    }
+
+    fn keys<C: Circuit<Fp>>(&self, k: u32, circuit: &C) -> Result<SharedKeys, JobError> {
+        let id = keys::circuit_id::<C>();
+        let slot = self
+            .slots
+            .lock()?
+            .entry((id.clone(), k))
+            .or_default()
+            .clone();
+
+        // a slot is only filled once keygen succeeded, so one left behind by a
+        // panicking keygen is still empty and safe to retry
+        let mut slot = slot.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(keys) = &*slot {
+            return Ok(keys.clone());
+        }
+
+        let (params, vk_path) = {
+            let mut cache = self.cache.lock()?;
+            (
+                cache.shared_params(k).map_err(KeyError::Io)?,
+                cache.vk_path(&id, k),
+            )
+        };
+        self.keygens.fetch_add(1, Ordering::Relaxed);
+        let pk = Arc::new(keys::keygen_fingerprinted(vk_path, &params, circuit)?);
+
+        *slot = Some((params.clone(), pk.clone()));
+        Ok((params, pk))
+    }
+}
+
+pub struct ProvingService {
+    cache: Arc<SharedCache>,
+    queue: Option<mpsc::Sender<Task>>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl ProvingService {
+    pub fn new(cache: KeyCache, workers: usize) -> Self {
+        let cache = Arc::new(SharedCache::new(cache));
+        let (queue, tasks) = mpsc::channel::<Task>();
+        let tasks = Arc::new(Mutex::new(tasks));
+
+        let workers = (0..workers.max(1))
+            .map(|_| {
+                let (cache, tasks) = (cache.clone(), tasks.clone());
+                thread::spawn(move || loop {
+                    // the lock is released before proving, so workers only queue up here
+                    let task = tasks.lock().unwrap_or_else(PoisonError::into_inner).recv();
+                    let Ok((job, reply)) = task else { break };
+                    let _ = reply.send(caught(|| run(&cache, &job)));
+                })
+            })
+            .collect();
+
+        Self {
+            cache,
+            queue: Some(queue),
+            workers,
+        }
+    }
+
+    // the keys the workers prove with, e.g. to verify their proofs
+    pub fn keys<C: Circuit<Fp>>(&self, k: u32, circuit: &C) -> Result<SharedKeys, JobError> {
+        self.cache.keys(k, circuit)
+    }
+
+    pub fn submit(&self, job: Job) -> JobHandle {
+        let (reply, result) = mpsc::channel();
+        if let Some(queue) = &self.queue {
+            // a failed send drops `reply`, which `wait` reports as cancelled
+            let _ = queue.send((job, reply));
+        }
+        JobHandle(result)
+    }
+
+    // submits all jobs before waiting, results come back in job order
+    pub fn prove_all(&self, jobs: impl IntoIterator<Item = Job>) -> Vec<JobResult> {
+        let handles: Vec<JobHandle> = jobs.into_iter().map(|job| self.submit(job)).collect();
+        handles.into_iter().map(JobHandle::wait).collect()
+    }
+}
+
+// finishes the queued jobs, then stops the workers
+impl Drop for ProvingService {
+    fn drop(&mut self) {
+        self.queue.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+// a panicking job fails on its own and leaves the worker running
+fn caught(job: impl FnOnce() -> JobResult) -> JobResult {
+    panic::catch_unwind(AssertUnwindSafe(job))
+        .unwrap_or_else(|payload| Err(JobError::from_panic(payload)))
+}
+
+fn run(cache: &SharedCache, job: &Job) -> JobResult {
+    circuits::visit(
+        &job.circuit,
+        &job.inputs,
+        ProveJob {
+            cache,
+            name: &job.circuit,
+            instances: &job.instances,
+            transcript: job.transcript,
+        },
+    )?
+}
+
+struct ProveJob<'a> {
+    cache: &'a SharedCache,
+    name: &'a str,
+    instances: &'a [Vec<Fp>],
+    transcript: TranscriptKind,
+}
+
+impl CircuitVisitor for ProveJob<'_> {
+    type Output = JobResult;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+        if self.instances != instances {
+            return Err(JobError::InstanceMismatch {
+                expected: instances,
+                found: self.instances.to_vec(),
+            });
+        }
+
+        // halo2 proves unsatisfied witnesses without complaint
+        let k = sizing::min_k(&circuit, &instances)?;
+        let report = diagnostics::check(k, &circuit, instances.clone())?;
+        if !report.is_ok() {
+            return Err(JobError::Unsatisfied(report));
+        }
+
+        let (params, pk) = self.cache.keys(k, &circuit)?;
+
+        let proof = self.transcript.visit(ProveWith {
+            keys: (&params, &pk),
+            circuit,
+            instances: &instances,
+        })?;
+
+        Ok(ProofEnvelope::new(
+            self.name,
+            k,
+            self.transcript,
+            pk.get_vk(),
+            vec![instances],
+            proof,
+        ))
+    }
+}
+
+struct ProveWith<'a, C> {
+    keys: (&'a Params<EqAffine>, &'a ProvingKey<EqAffine>),
+    circuit: C,
+    instances: &'a [Vec<Fp>],
+}
+
+impl<C: Circuit<Fp>> TranscriptVisitor for ProveWith<'_, C> {
+    type Output = Result<Vec<u8>, Error>;
+
+    fn visit<T: TranscriptHash>(self) -> Self::Output {
+        let (params, pk) = self.keys;
+        let columns: Vec<&[Fp]> = self.instances.iter().map(|column| &column[..]).collect();
+        prover::prove_with::<T, C>(params, pk, &[self.circuit], &[&columns])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use halo2_proofs::{pasta::Fp, plonk::Circuit};
+
+    use std::sync::atomic::Ordering;
+
+    use super::{caught, Job, JobError, ProvingService};
+    use crate::{
+        circuits::{self, CircuitError, CircuitVisitor, Inputs},
+        envelope::ProofEnvelope,
+        keys::KeyCache,
+        temp_dir::TempDir,
+        transcript::TranscriptKind,
+    };
+
+    fn job(circuit: &str, a: u64, b: u64) -> Job {
+        let (a, b) = (Fp::from(a), Fp::from(b));
+        let inputs: Inputs = [("a".to_string(), a), ("b".to_string(), b)].into();
+        let instances = match circuit {
+            "fibonacci" => vec![vec![a, b, circuits::fibonacci(a, b, 9)]],
+            _ => vec![vec![a, b, a + b]],
+        };
+        Job {
+            circuit: circuit.to_string(),
+            inputs,
+            instances,
+            transcript: TranscriptKind::Blake2b,
+        }
+    }
+
+    // verifies with keys from the cache the service wrote to
+    struct Verify<'a> {
+        cache: &'a mut KeyCache,
+        envelope: &'a ProofEnvelope,
+    }
+
+    impl CircuitVisitor for Verify<'_> {
+        type Output = bool;
+
+        fn visit<C: Circuit<Fp>>(self, circuit: C, _: Vec<Vec<Fp>>) -> bool {
+            let envelope = self.envelope;
+            let (params, pk) = self.cache.get_or_keygen(envelope.k, &circuit).unwrap();
+            envelope
+                .verify(&envelope.circuit, envelope.k, params, pk.get_vk())
+                .is_ok()
+        }
+    }
+
+    #[test]
+    fn test_prove_all() {
+        let dir = TempDir::new("service");
+        let service = ProvingService::new(KeyCache::new(dir.path()).unwrap(), 4);
+
+        let jobs: Vec<Job> = (0..8)
+            .map(|i| {
+                let mut job = job(if i % 2 == 0 { "add" } else { "fibonacci" }, i, i + 1);
+                if i >= 4 {
+                    job.transcript = TranscriptKind::Poseidon;
+                }
+                job
+            })
+            .collect();
+        let results = service.prove_all(jobs.clone());
+
+        let mut cache = KeyCache::new(dir.path()).unwrap();
+        for (job, result) in jobs.iter().zip(results) {
+            let envelope = result.unwrap();
+            assert_eq!(envelope.instances, vec![job.instances.clone()]);
+            assert_eq!(envelope.transcript, job.transcript);
+
+            let verify = Verify {
+                cache: &mut cache,
+                envelope: &envelope,
+            };
+            assert!(circuits::visit(&job.circuit, &job.inputs, verify).unwrap());
+        }
+
+        // once per circuit, whatever the transcript
+        assert_eq!(service.cache.keygens.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_errors() {
+        let dir = TempDir::new("service-errors");
+        let cache = KeyCache::new(dir.path()).unwrap();
+        let service = ProvingService::new(cache, 2);
+
+        let mut unknown = job("add", 1, 2);
+        unknown.circuit = "nope".to_string();
+        let res = service.submit(unknown).wait();
+        assert!(matches!(
+            res,
+            Err(JobError::Circuit(CircuitError::UnknownCircuit(_)))
+        ));
+
+        let mut missing = job("add", 1, 2);
+        missing.inputs.remove("b");
+        let res = service.submit(missing).wait();
+        assert!(matches!(
+            res,
+            Err(JobError::Circuit(CircuitError::MissingInput { .. }))
+        ));
+
+        // claim a wrong sum
+        let mut wrong = job("add", 1, 2);
+        wrong.instances[0][2] = Fp::from(4);
+        let res = service.submit(wrong).wait();
+        assert!(matches!(res, Err(JobError::InstanceMismatch { .. })));
+
+        // instances that follow from inputs the circuit rejects
+        let mut unequal = job("equality", 0, 0);
+        unequal.inputs = [
+            ("input".to_string(), Fp::from(1)),
+            ("output".to_string(), Fp::from(2)),
+        ]
+        .into();
+        unequal.instances = vec![vec![Fp::from(1), Fp::from(2)]];
+        match service.submit(unequal).wait() {
+            Err(JobError::Unsatisfied(report)) => {
+                assert!(report.to_string().contains("equal gate"), "{}", report)
+            }
+            other => panic!("expected an unsatisfied job, got {:?}", other.map(|_| ())),
+        }
+
+        // failed jobs do not take the workers down
+        assert!(service.submit(job("add", 1, 2)).wait().is_ok());
+    }
+
+    #[test]
+    fn test_panic() {
+        let res = caught(|| panic!("boom"));
+        assert!(matches!(res, Err(JobError::Panicked(message)) if message == "boom"));
+
+        let dir = TempDir::new("service-panic");
+        let cache = KeyCache::new(dir.path()).unwrap();
+        let service = ProvingService::new(cache, 1);
+
+        // a panic while the key cache is locked fails later jobs instead of the workers
+        let cache = service.cache.clone();
+        let _ = thread::spawn(move || {
+            let _guard = cache.cache.lock().unwrap();
+            panic!("poison");
+        })
+        .join();
+        let res = service.submit(job("fibonacci", 1, 1)).wait();
+        assert!(matches!(res, Err(JobError::Panicked(_))));
+    }
+}
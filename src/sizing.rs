@@ -0,0 +1,89 @@
+// smallest k a circuit fits in, found by recording its layout
+use halo2_proofs::{
+    pasta::Fp,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use crate::recorder;
+
+// rows the circuit assigns, copies into or enables a selector on
+pub fn rows_used<C: Circuit<Fp>>(circuit: &C) -> Result<usize, Error> {
+    Ok(recorder::record(circuit, &[])?.rows())
+}
+
+// first and last row of every region in the order they were entered, None for
+// regions that assign nothing
+pub fn region_rows<C: Circuit<Fp>>(circuit: &C) -> Result<Vec<Option<(usize, usize)>>, Error> {
+    let recording = recorder::record(circuit, &[])?;
+    Ok(recording.regions.iter().map(|region| region.rows).collect())
+}
+
+// smallest k for which MockProver and the real prover accept the circuit with `instances`
+pub fn min_k<C: Circuit<Fp>>(circuit: &C, instances: &[Vec<Fp>]) -> Result<u32, Error> {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    C::configure(&mut cs);
+
+    // the last blinding_factors + 1 rows are reserved
+    let reserved = cs.blinding_factors() + 1;
+    let instance_rows = instances
+        .iter()
+        .map(|column| column.len())
+        .max()
+        .unwrap_or(0);
+    let needed = (rows_used(circuit)?.max(instance_rows) + reserved).max(cs.minimum_rows());
+
+    Ok(needed.next_power_of_two().trailing_zeros())
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::Value,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Error},
+    };
+
+    use super::{min_k, rows_used};
+    use crate::{
+        circuits::{self, CircuitVisitor},
+        example4,
+    };
+
+    #[derive(Clone, Copy)]
+    struct Fits;
+
+    impl CircuitVisitor for Fits {
+        type Output = u32;
+
+        fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> u32 {
+            let k = min_k(&circuit, &instances).unwrap();
+            MockProver::run(k, &circuit, instances.clone())
+                .unwrap()
+                .assert_satisfied();
+            assert!(matches!(
+                MockProver::run(k - 1, &circuit, instances),
+                Err(Error::NotEnoughRowsAvailable { .. }) | Err(Error::InstanceTooLarge)
+            ));
+            k
+        }
+    }
+
+    #[test]
+    fn test_min_k() {
+        circuits::tests::each_with_constant_inputs(Fp::from(3), Fits);
+    }
+
+    #[test]
+    fn test_instance_rows() {
+        let circuit = example4::AddCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(2)),
+        };
+        assert_eq!(rows_used(&circuit).unwrap(), 3);
+
+        // a long instance column needs more rows than the circuit itself
+        let k = min_k(&circuit, &[vec![Fp::zero(); 20]]).unwrap();
+        assert_eq!(k, 5);
+    }
+}
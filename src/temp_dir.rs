@@ -0,0 +1,28 @@
+// a scratch directory for one test, removed again when the test ends. The
+// integration tests include this file by path, so it only uses std
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = env::temp_dir().join(format!("halo2-example-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
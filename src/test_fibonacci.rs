@@ -3,17 +3,17 @@ use std::marker::PhantomData;
 use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
 
 #[derive(Debug, Clone)]
-struct ACell<F: FieldExt>(AssignedCell<F, F>);
+pub struct ACell<F: FieldExt>(AssignedCell<F, F>);
 
 #[derive(Debug, Clone)]
-struct FiboConfig {
+pub struct FiboConfig {
     pub advice: [Column<Advice>; 3],
     pub selector: Selector,
     pub instance: Column<Instance>,
 }
 
 #[derive(Debug, Clone)]
-struct FiboChip<F: FieldExt> {
+pub struct FiboChip<F: FieldExt> {
     config: FiboConfig,
     _marker: PhantomData<F>,
 }
@@ -123,13 +123,13 @@ impl<F: FieldExt> FiboChip<F> {
         mut layouter: impl Layouter<F>,
         cell: &ACell<F>,
         row: usize,
-    ) -> Result<(), Error>{
+    ) -> Result<(), Error> {
         layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
     }
 }
 
 #[derive(Default)]
-struct MyCircuit<F> {
+pub struct MyCircuit<F> {
     pub a: Value<F>,
     pub b: Value<F>,
 }
@@ -178,30 +178,43 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
 #[cfg(test)]
 mod tests {
     use super::MyCircuit;
-    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+    use crate::{
+        arbitrary, circuits,
+        layout::{Format, Layout},
+    };
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+    use proptest::proptest;
+
+    proptest! {
+        #![proptest_config(arbitrary::config())]
+
+        #[test]
+        fn test_example1(a in arbitrary::fp(), b in arbitrary::fp(), delta in arbitrary::nonzero()) {
+            let circuit = MyCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+            // F[0], F[1] and F[9]
+            let public_input = vec![vec![a, b, circuits::fibonacci(a, b, 9)]];
+            arbitrary::assert_sound(&circuit, public_input, delta);
+        }
+    }
 
+    // `cargo run -- layout --circuit <name> --format html --out <file>` writes the
+    // page for viewing
     #[test]
-    fn test_example1() {
-        let k = 4;
-
-        let a = Fp::from(1); // F[0]
-        let b = Fp::from(1); // F[1]
-        let out = Fp::from(55); // F[9]
-
-        let circuit = MyCircuit {
-            a: Value::known(a),
-            b: Value::known(b),
+    fn test_layout_html() {
+        let circuit = MyCircuit::<Fp> {
+            a: Value::unknown(),
+            b: Value::unknown(),
         };
+        let layout = Layout::of(&circuit, &[]).unwrap();
 
-        let mut public_input = vec![a, b, out];
-
-        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
-        prover.assert_satisfied();
-
-        public_input[2] += Fp::one();
-        let _prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
-        // uncomment the following line and the assert will fail
-        // _prover.assert_satisfied();
+        let html = layout.render(Format::Html, "Fib 1 Layout");
+        assert!(html.contains("<h1>Fib 1 Layout</h1>"));
+        for region in &layout.recording.regions {
+            assert!(html.contains(&region.name));
+        }
     }
 
     #[cfg(feature = "dev-graph")]
@@ -221,4 +234,4 @@ mod tests {
             .render(4, &circuit, &root)
             .unwrap();
     }
-}
\ No newline at end of file
+}
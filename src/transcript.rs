@@ -0,0 +1,420 @@
+// fiat-shamir transcripts besides halo2's Blake2b, picked by type parameter in `prover`
+// or at runtime by `TranscriptKind`
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    str::FromStr,
+};
+
+use ff::PrimeField;
+use group::GroupEncoding;
+use halo2_proofs::{
+    arithmetic::{Coordinates, CurveAffine, FieldExt},
+    pasta::{EqAffine, Fp},
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, Transcript, TranscriptRead,
+        TranscriptWrite,
+    },
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256 as Sha256Hasher};
+
+use crate::poseidon;
+
+// same domain separation as halo2's Blake2b transcript
+const PREFIX_CHALLENGE: u8 = 0;
+const PREFIX_POINT: u8 = 1;
+const PREFIX_SCALAR: u8 = 2;
+
+pub type Challenge = Challenge255<EqAffine>;
+
+// a hash that proofs can be written and read with
+pub trait TranscriptHash {
+    const KIND: TranscriptKind;
+
+    type Writer: TranscriptWrite<EqAffine, Challenge>;
+    type Reader<'a>: TranscriptRead<EqAffine, Challenge>;
+
+    fn writer() -> Self::Writer;
+    fn finalize(writer: Self::Writer) -> Vec<u8>;
+    fn reader(proof: &[u8]) -> Self::Reader<'_>;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Blake2b;
+
+impl TranscriptHash for Blake2b {
+    const KIND: TranscriptKind = TranscriptKind::Blake2b;
+
+    type Writer = Blake2bWrite<Vec<u8>, EqAffine, Challenge>;
+    type Reader<'a> = Blake2bRead<&'a [u8], EqAffine, Challenge>;
+
+    fn writer() -> Self::Writer {
+        Blake2bWrite::init(vec![])
+    }
+
+    fn finalize(writer: Self::Writer) -> Vec<u8> {
+        writer.finalize()
+    }
+
+    fn reader(proof: &[u8]) -> Self::Reader<'_> {
+        Blake2bRead::init(proof)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sha256;
+
+impl TranscriptHash for Sha256 {
+    const KIND: TranscriptKind = TranscriptKind::Sha256;
+
+    type Writer = HashWrite<Vec<u8>, Sha256State>;
+    type Reader<'a> = HashRead<&'a [u8], Sha256State>;
+
+    fn writer() -> Self::Writer {
+        HashWrite::init(vec![])
+    }
+
+    fn finalize(writer: Self::Writer) -> Vec<u8> {
+        writer.finalize()
+    }
+
+    fn reader(proof: &[u8]) -> Self::Reader<'_> {
+        HashRead::init(proof)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Poseidon;
+
+impl TranscriptHash for Poseidon {
+    const KIND: TranscriptKind = TranscriptKind::Poseidon;
+
+    type Writer = HashWrite<Vec<u8>, PoseidonState>;
+    type Reader<'a> = HashRead<&'a [u8], PoseidonState>;
+
+    fn writer() -> Self::Writer {
+        HashWrite::init(vec![])
+    }
+
+    fn finalize(writer: Self::Writer) -> Vec<u8> {
+        writer.finalize()
+    }
+
+    fn reader(proof: &[u8]) -> Self::Reader<'_> {
+        HashRead::init(proof)
+    }
+}
+
+// the transcript hash a proof was made with, recorded in proof envelopes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptKind {
+    #[default]
+    Blake2b,
+    Sha256,
+    Poseidon,
+}
+
+// runs generic code with the `TranscriptHash` a `TranscriptKind` names
+pub trait TranscriptVisitor {
+    type Output;
+
+    fn visit<T: TranscriptHash>(self) -> Self::Output;
+}
+
+impl TranscriptKind {
+    pub const ALL: [TranscriptKind; 3] = [
+        TranscriptKind::Blake2b,
+        TranscriptKind::Sha256,
+        TranscriptKind::Poseidon,
+    ];
+
+    // stable byte for the binary envelope
+    pub fn id(self) -> u8 {
+        match self {
+            TranscriptKind::Blake2b => 0,
+            TranscriptKind::Sha256 => 1,
+            TranscriptKind::Poseidon => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TranscriptKind::Blake2b => "blake2b",
+            TranscriptKind::Sha256 => "sha256",
+            TranscriptKind::Poseidon => "poseidon",
+        }
+    }
+
+    pub fn visit<V: TranscriptVisitor>(self, visitor: V) -> V::Output {
+        match self {
+            TranscriptKind::Blake2b => visitor.visit::<Blake2b>(),
+            TranscriptKind::Sha256 => visitor.visit::<Sha256>(),
+            TranscriptKind::Poseidon => visitor.visit::<Poseidon>(),
+        }
+    }
+}
+
+impl fmt::Display for TranscriptKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for TranscriptKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown transcript {:?}, expected blake2b, sha256 or poseidon",
+                    s
+                )
+            })
+    }
+}
+
+// running hash of a transcript
+pub trait TranscriptState {
+    fn init() -> Self;
+    fn absorb_point(&mut self, point: EqAffine) -> io::Result<()>;
+    fn absorb_scalar(&mut self, scalar: Fp);
+    fn squeeze(&mut self) -> Challenge;
+}
+
+fn coordinates(point: EqAffine) -> io::Result<Coordinates<EqAffine>> {
+    Option::from(point.coordinates())
+        .ok_or_else(|| io::Error::other("cannot write points at infinity to the transcript"))
+}
+
+#[derive(Debug, Clone)]
+pub struct Sha256State(Sha256Hasher);
+
+impl TranscriptState for Sha256State {
+    fn init() -> Self {
+        Self(Sha256Hasher::new_with_prefix(b"Halo2-Transcript"))
+    }
+
+    fn absorb_point(&mut self, point: EqAffine) -> io::Result<()> {
+        let coords = coordinates(point)?;
+        self.0.update([PREFIX_POINT]);
+        self.0.update(coords.x().to_repr());
+        self.0.update(coords.y().to_repr());
+        Ok(())
+    }
+
+    fn absorb_scalar(&mut self, scalar: Fp) {
+        self.0.update([PREFIX_SCALAR]);
+        self.0.update(scalar.to_repr());
+    }
+
+    // Challenge255 wants 64 bytes, so two digests of the state with distinct suffixes
+    fn squeeze(&mut self) -> Challenge {
+        self.0.update([PREFIX_CHALLENGE]);
+
+        let mut result = [0u8; 64];
+        for (i, half) in result.chunks_mut(32).enumerate() {
+            let mut hasher = self.0.clone();
+            hasher.update([i as u8]);
+            half.copy_from_slice(&hasher.finalize());
+        }
+        Challenge255::new(&result)
+    }
+}
+
+// Poseidon over Fp, the scalar field, so a verifier circuit over Fp can recompute
+// the challenges. Point coordinates live in Fq and do not fit in Fp, so each is
+// absorbed as two 128 bit limbs.
+#[derive(Debug, Clone)]
+pub struct PoseidonState(poseidon::Sponge);
+
+impl TranscriptState for PoseidonState {
+    fn init() -> Self {
+        let domain = u128::from_le_bytes(*b"Halo2-Transcript");
+        Self(poseidon::Sponge::new(Fp::from_u128(domain)))
+    }
+
+    fn absorb_point(&mut self, point: EqAffine) -> io::Result<()> {
+        let coords = coordinates(point)?;
+        self.0.absorb(Fp::from(PREFIX_POINT as u64));
+        for coord in [coords.x(), coords.y()] {
+            let repr = coord.to_repr();
+            for limb in repr.chunks(16) {
+                let limb = u128::from_le_bytes(limb.try_into().unwrap());
+                self.0.absorb(Fp::from_u128(limb));
+            }
+        }
+        Ok(())
+    }
+
+    fn absorb_scalar(&mut self, scalar: Fp) {
+        self.0.absorb(Fp::from(PREFIX_SCALAR as u64));
+        self.0.absorb(scalar);
+    }
+
+    // the squeezed element padded to 64 bytes reduces back to itself in Challenge255
+    fn squeeze(&mut self) -> Challenge {
+        self.0.absorb(Fp::from(PREFIX_CHALLENGE as u64));
+
+        let mut result = [0u8; 64];
+        result[..32].copy_from_slice(&self.0.squeeze().to_repr());
+        Challenge255::new(&result)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HashWrite<W: Write, S: TranscriptState> {
+    state: S,
+    writer: W,
+}
+
+impl<W: Write, S: TranscriptState> HashWrite<W, S> {
+    pub fn init(writer: W) -> Self {
+        Self {
+            state: S::init(),
+            writer,
+        }
+    }
+
+    pub fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write, S: TranscriptState> Transcript<EqAffine, Challenge> for HashWrite<W, S> {
+    fn squeeze_challenge(&mut self) -> Challenge {
+        self.state.squeeze()
+    }
+
+    fn common_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.state.absorb_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.state.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl<W: Write, S: TranscriptState> TranscriptWrite<EqAffine, Challenge> for HashWrite<W, S> {
+    fn write_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(point.to_bytes().as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(scalar.to_repr().as_ref())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HashRead<R: Read, S: TranscriptState> {
+    state: S,
+    reader: R,
+}
+
+impl<R: Read, S: TranscriptState> HashRead<R, S> {
+    pub fn init(reader: R) -> Self {
+        Self {
+            state: S::init(),
+            reader,
+        }
+    }
+}
+
+impl<R: Read, S: TranscriptState> Transcript<EqAffine, Challenge> for HashRead<R, S> {
+    fn squeeze_challenge(&mut self) -> Challenge {
+        self.state.squeeze()
+    }
+
+    fn common_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.state.absorb_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.state.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read, S: TranscriptState> TranscriptRead<EqAffine, Challenge> for HashRead<R, S> {
+    fn read_point(&mut self) -> io::Result<EqAffine> {
+        let mut compressed = <EqAffine as GroupEncoding>::Repr::default();
+        self.reader.read_exact(compressed.as_mut())?;
+        let point = Option::from(EqAffine::from_bytes(&compressed))
+            .ok_or_else(|| io::Error::other("invalid point encoding in proof"))?;
+        self.common_point(point)?;
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<Fp> {
+        let mut data = <Fp as PrimeField>::Repr::default();
+        self.reader.read_exact(data.as_mut())?;
+        let scalar = Option::from(Fp::from_repr(data))
+            .ok_or_else(|| io::Error::other("invalid field element encoding in proof"))?;
+        self.common_scalar(scalar)?;
+        Ok(scalar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        pasta::{EqAffine, Fp},
+        poly::commitment::Params,
+        transcript::{EncodedChallenge, Transcript, TranscriptRead, TranscriptWrite},
+    };
+
+    use super::{Poseidon, Sha256, TranscriptHash, TranscriptKind};
+
+    fn round_trip<T: TranscriptHash>() {
+        let point = Params::<EqAffine>::new(1).get_g()[0];
+        let scalar = Fp::from(7);
+
+        let mut writer = T::writer();
+        writer.write_point(point).unwrap();
+        writer.write_scalar(scalar).unwrap();
+        let challenge = writer.squeeze_challenge().get_scalar();
+        let proof = T::finalize(writer);
+
+        let mut reader = T::reader(&proof);
+        assert_eq!(reader.read_point().unwrap(), point);
+        assert_eq!(reader.read_scalar().unwrap(), scalar);
+        assert_eq!(reader.squeeze_challenge().get_scalar(), challenge);
+
+        // a different transcript gives a different challenge
+        let mut reader = T::reader(&proof);
+        reader.read_point().unwrap();
+        reader.common_scalar(Fp::from(8)).unwrap();
+        assert_ne!(reader.squeeze_challenge().get_scalar(), challenge);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip::<Sha256>();
+        round_trip::<Poseidon>();
+    }
+
+    #[test]
+    fn test_kind() {
+        for kind in TranscriptKind::ALL {
+            assert_eq!(TranscriptKind::from_id(kind.id()), Some(kind));
+            assert_eq!(kind.to_string().parse::<TranscriptKind>(), Ok(kind));
+        }
+        assert_eq!(TranscriptKind::from_id(3), None);
+        assert!("keccak".parse::<TranscriptKind>().is_err());
+        assert_eq!(
+            serde_json::to_string(&TranscriptKind::Poseidon).unwrap(),
+            "\"poseidon\""
+        );
+    }
+}
@@ -0,0 +1,537 @@
+// finds advice cells a circuit does not pin down: cells no enabled gate, lookup
+// or copy constraint reads, and cells MockProver still accepts with another value
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, HashSet},
+    fmt,
+    marker::PhantomData,
+};
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    pasta::Fp,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostics::{self, ColumnRef, Named, Report},
+    inspect::{self, ColumnKind, CsInfo, Expr},
+    recorder::{self, Recording},
+    sizing,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CellFinding {
+    pub row: usize,
+    pub offset: Option<usize>,
+    pub name: String,
+    // no enabled gate, lookup or copy constraint reads the cell
+    pub unreferenced: bool,
+    // MockProver still accepts the circuit with the cell's value changed
+    pub free: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnFindings {
+    pub column: ColumnRef,
+    pub cells: Vec<CellFinding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionFindings {
+    // None for cells assigned outside any region
+    pub region: Option<Named>,
+    pub columns: Vec<ColumnFindings>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Analysis {
+    pub regions: Vec<RegionFindings>,
+}
+
+#[derive(Debug)]
+pub enum AnalysisError {
+    Synthesis(Error),
+    // cells can only be checked for freedom against a satisfied circuit
+    Unsatisfied(Report),
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::Synthesis(e) => write!(f, "{}", e),
+            AnalysisError::Unsatisfied(report) => {
+                write!(f, "circuit is not satisfied to begin with\n{}", report)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+impl From<Error> for AnalysisError {
+    fn from(e: Error) -> Self {
+        AnalysisError::Synthesis(e)
+    }
+}
+
+pub fn analyze<C: Circuit<Fp>>(
+    circuit: &C,
+    instances: Vec<Vec<Fp>>,
+) -> Result<Analysis, AnalysisError> {
+    let k = sizing::min_k(circuit, &instances)?;
+    let report = diagnostics::check(k, circuit, instances.clone())?;
+    if !report.is_ok() {
+        return Err(AnalysisError::Unsatisfied(report));
+    }
+
+    let info = CsInfo::of::<C>();
+    let recording = recorder::record(circuit, &instances)?;
+    let usable_rows = (1 << k) - info.blinding_factors - 1;
+    let referenced = referenced_cells(&info, &recording, usable_rows);
+
+    let mut findings: BTreeMap<(Option<usize>, usize), Vec<CellFinding>> = BTreeMap::new();
+    for cell in &recording.cells {
+        let position = cell.position;
+        if position.kind != ColumnKind::Advice {
+            continue;
+        }
+
+        let unreferenced = !referenced.contains(&(position.column, position.row));
+        let free = {
+            let _target = Target::set(position.column, position.row);
+            diagnostics::check(k, &Tampered(circuit), instances.clone())?.is_ok()
+        };
+        if !(unreferenced || free) {
+            continue;
+        }
+
+        let start = cell.region.and_then(|r| recording.regions[r].rows);
+        findings
+            .entry((cell.region, position.column))
+            .or_default()
+            .push(CellFinding {
+                row: position.row,
+                offset: start.map(|(start, _)| position.row - start),
+                name: cell.name.clone(),
+                unreferenced,
+                free,
+            });
+    }
+
+    let mut analysis = Analysis::default();
+    for ((region, column), cells) in findings {
+        let named = region.map(|index| Named {
+            index,
+            name: recording.regions[index].name.clone(),
+        });
+        if analysis.regions.last().map(|r| &r.region) != Some(&named) {
+            analysis.regions.push(RegionFindings {
+                region: named,
+                columns: vec![],
+            });
+        }
+        let column = ColumnRef {
+            kind: ColumnKind::Advice,
+            index: column,
+        };
+        let region = analysis.regions.last_mut().expect("pushed above");
+        region.columns.push(ColumnFindings { column, cells });
+    }
+    Ok(analysis)
+}
+
+impl Analysis {
+    pub fn is_ok(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("analyses serialize")
+    }
+}
+
+// (advice column, row) of every cell an enabled gate, a lookup or a copy reads
+fn referenced_cells(
+    info: &CsInfo,
+    recording: &Recording,
+    usable_rows: usize,
+) -> HashSet<(usize, usize)> {
+    let mut cells = HashSet::new();
+    let mut mark = |exprs: &[Expr], rows: &[usize]| {
+        for expr in exprs {
+            for (kind, column, rotation) in expr.queries() {
+                for row in rows {
+                    let row = *row as i64 + rotation as i64;
+                    if kind == ColumnKind::Advice && row >= 0 {
+                        cells.insert((column, row as usize));
+                    }
+                }
+            }
+        }
+    };
+
+    // an expression applies on the rows one of its selectors is enabled on, or
+    // on every usable row when it has none
+    let active_rows = |exprs: &[Expr]| -> Vec<usize> {
+        let selectors: Vec<usize> = exprs.iter().flat_map(Expr::selectors).collect();
+        if selectors.is_empty() {
+            return (0..usable_rows).collect();
+        }
+        recording
+            .selectors
+            .iter()
+            .filter(|s| selectors.contains(&s.selector))
+            .map(|s| s.row)
+            .collect()
+    };
+
+    for constraint in &info.constraints {
+        let rows = active_rows(std::slice::from_ref(constraint));
+        mark(std::slice::from_ref(constraint), &rows);
+    }
+    for lookup in &info.lookups {
+        mark(&lookup.input, &active_rows(&lookup.input));
+        mark(&lookup.table, &(0..usable_rows).collect::<Vec<_>>());
+    }
+    for position in recording.copies.iter().flat_map(|(l, r)| [l, r]) {
+        if position.kind == ColumnKind::Advice {
+            cells.insert((position.column, position.row));
+        }
+    }
+    cells
+}
+
+// MockProver lays the circuit out itself, so the cell to change reaches the
+// floor planner through a thread local rather than through the circuit
+thread_local! {
+    static TARGET: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+struct Target;
+
+impl Target {
+    fn set(column: usize, row: usize) -> Self {
+        TARGET.with(|t| t.set(Some((column, row))));
+        Target
+    }
+}
+
+impl Drop for Target {
+    fn drop(&mut self) {
+        TARGET.with(|t| t.set(None));
+    }
+}
+
+// the wrapped circuit with one more added to the target advice cell
+struct Tampered<'a, C>(&'a C);
+
+impl<F: Field, C: Circuit<F>> Circuit<F> for Tampered<'_, C> {
+    type Config = C::Config;
+    type FloorPlanner = Tamper<C::FloorPlanner>;
+
+    fn without_witnesses(&self) -> Self {
+        Tampered(self.0)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+struct Tamper<P>(PhantomData<P>);
+
+impl<P: FloorPlanner> FloorPlanner for Tamper<P> {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let mut tampering = Tampering {
+            cs,
+            target: TARGET.with(Cell::get),
+            _marker: PhantomData,
+        };
+        P::synthesize(&mut tampering, circuit, config, constants)
+    }
+}
+
+// forwards to `cs`, changing the value assigned to `target`
+struct Tampering<'a, F, CS> {
+    cs: &'a mut CS,
+    target: Option<(usize, usize)>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field, CS: Assignment<F>> Assignment<F> for Tampering<'_, F, CS> {
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.enter_region(name)
+    }
+
+    fn exit_region(&mut self) {
+        self.cs.exit_region()
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.enable_selector(annotation, selector, row)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let (_, index) = inspect::column_id(column);
+        if self.target == Some((index, row)) {
+            let to = || to().map(|v| v.into() + F::one());
+            self.cs.assign_advice(annotation, column, row, to)
+        } else {
+            self.cs.assign_advice(annotation, column, row, to)
+        }
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.assign_fixed(annotation, column, row, to)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}
+
+impl fmt::Display for CellFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}", self.row)?;
+        if let Some(offset) = self.offset {
+            write!(f, " (offset {})", offset)?;
+        }
+        if !self.name.is_empty() {
+            write!(f, " '{}'", self.name)?;
+        }
+        let problems = match (self.unreferenced, self.free) {
+            (true, true) => "unreferenced, free",
+            (true, false) => "unreferenced",
+            _ => "free",
+        };
+        write!(f, ": {}", problems)
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return writeln!(f, "every advice cell is constrained");
+        }
+        for region in &self.regions {
+            match &region.region {
+                Some(named) => writeln!(f, "region {}", named)?,
+                None => writeln!(f, "outside any region")?,
+            }
+            for column in &region.columns {
+                writeln!(f, "  {}", column.column)?;
+                for cell in &column.cells {
+                    writeln!(f, "    {}", cell)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{analyze, Analysis, AnalysisError};
+    use crate::{
+        circuits::{self, CircuitVisitor},
+        example1, example2,
+    };
+
+    // example2's equality gate, but the selector is never enabled and only the
+    // input is made public
+    #[derive(Default)]
+    struct Unselected {
+        input: Value<Fp>,
+        output: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for Unselected {
+        type Config = example2::AddConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            example2::AddChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let input = layouter.assign_region(
+                || "unselected",
+                |mut region| {
+                    region.assign_advice(|| "output", config.advice[1], 0, || self.output)?;
+                    region.assign_advice(|| "input", config.advice[0], 0, || self.input)
+                },
+            )?;
+            layouter.constrain_instance(input.cell(), config.instance[0], 0)
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    struct Analyze;
+
+    impl CircuitVisitor for Analyze {
+        type Output = Result<Analysis, AnalysisError>;
+
+        fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+            analyze(&circuit, instances)
+        }
+    }
+
+    #[test]
+    fn test_registered_circuits() {
+        for (name, analysis) in circuits::tests::each_with_constant_inputs(Fp::from(2), Analyze) {
+            assert!(analysis.unwrap().is_ok(), "{}", name);
+        }
+    }
+
+    // the two circuits once labeled "not working"
+    #[test]
+    fn test_example1_and_2() {
+        let (a, b) = (Fp::from(5), Fp::from(7));
+        let circuit = example1::AddCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        let analysis = analyze(&circuit, vec![vec![a], vec![b], vec![a + b]]).unwrap();
+        assert!(analysis.is_ok(), "{}", analysis);
+
+        let circuit = example2::AddCircuit {
+            input: Value::known(a),
+            output: Value::known(a),
+        };
+        let analysis = analyze(&circuit, vec![vec![a], vec![a]]).unwrap();
+        assert!(analysis.is_ok(), "{}", analysis);
+    }
+
+    #[test]
+    fn test_unselected_gate() {
+        let x = Fp::from(3);
+        let circuit = Unselected {
+            input: Value::known(x),
+            output: Value::known(x),
+        };
+        let analysis = analyze(&circuit, vec![vec![x], vec![]]).unwrap();
+
+        assert_eq!(analysis.regions.len(), 1);
+        let region = &analysis.regions[0];
+        assert_eq!(region.region.as_ref().unwrap().name, "unselected");
+        assert_eq!(region.columns.len(), 1);
+        assert_eq!(region.columns[0].column.to_string(), "advice[1]");
+
+        let cell = &region.columns[0].cells[0];
+        assert_eq!((cell.row, cell.offset), (0, Some(0)));
+        assert!(cell.unreferenced && cell.free);
+
+        let parsed: Analysis = serde_json::from_str(&analysis.to_json()).unwrap();
+        assert_eq!(parsed, analysis);
+        assert!(analysis
+            .to_string()
+            .contains("row 0 (offset 0) 'output': unreferenced, free"));
+    }
+
+    #[test]
+    fn test_unsatisfied() {
+        let circuit = Unselected {
+            input: Value::known(Fp::from(3)),
+            output: Value::known(Fp::from(3)),
+        };
+        let res = analyze(&circuit, vec![vec![Fp::from(4)], vec![]]);
+        assert!(matches!(res, Err(AnalysisError::Unsatisfied(_))));
+    }
+}
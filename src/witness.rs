@@ -0,0 +1,245 @@
+// the witness a circuit produces for one set of inputs, cell by cell: every
+// advice, fixed and instance value and every enabled selector, with the region
+// and annotation it was assigned under. Exported as csv or json
+use std::{fmt, fmt::Write, str::FromStr};
+
+use halo2_proofs::{
+    pasta::Fp,
+    plonk::{Circuit, Error},
+};
+use serde::Serialize;
+
+use crate::{
+    field::fp_to_hex,
+    inspect::ColumnKind,
+    recorder::{self, Recording},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            other => Err(format!(
+                "unknown witness format {:?}, expected csv or json",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CellKind {
+    Advice,
+    Fixed,
+    Instance,
+    Selector,
+}
+
+impl From<ColumnKind> for CellKind {
+    fn from(kind: ColumnKind) -> Self {
+        match kind {
+            ColumnKind::Advice => CellKind::Advice,
+            ColumnKind::Fixed => CellKind::Fixed,
+            ColumnKind::Instance => CellKind::Instance,
+        }
+    }
+}
+
+impl fmt::Display for CellKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellKind::Advice => write!(f, "advice"),
+            CellKind::Fixed => write!(f, "fixed"),
+            CellKind::Instance => write!(f, "instance"),
+            CellKind::Selector => write!(f, "selector"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry {
+    pub row: usize,
+    pub kind: CellKind,
+    pub column: usize,
+    pub region: Option<String>,
+    pub name: String,
+    // hex, None for cells assigned an unknown value
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Witness {
+    pub entries: Vec<Entry>,
+}
+
+impl Witness {
+    pub fn of<C: Circuit<Fp>>(circuit: &C, instances: &[Vec<Fp>]) -> Result<Self, Error> {
+        Ok(Self::from_recording(&recorder::record(circuit, instances)?))
+    }
+
+    // sorted by row, then column. A cell assigned twice keeps both assignments
+    pub fn from_recording(recording: &Recording) -> Self {
+        let region = |index: Option<usize>| index.map(|i| recording.regions[i].name.clone());
+        let mut entries = vec![];
+
+        for cell in &recording.cells {
+            entries.push(Entry {
+                row: cell.position.row,
+                kind: cell.position.kind.into(),
+                column: cell.position.column,
+                region: region(cell.region),
+                name: cell.name.clone(),
+                value: cell.value.as_ref().map(fp_to_hex),
+            });
+        }
+        for selector in &recording.selectors {
+            entries.push(Entry {
+                row: selector.row,
+                kind: CellKind::Selector,
+                column: selector.selector,
+                region: region(selector.region),
+                name: selector.name.clone(),
+                value: Some(fp_to_hex(&Fp::one())),
+            });
+        }
+        for (column, values) in recording.instances.iter().enumerate() {
+            for (row, value) in values.iter().enumerate() {
+                entries.push(Entry {
+                    row,
+                    kind: CellKind::Instance,
+                    column,
+                    region: None,
+                    name: String::new(),
+                    value: Some(fp_to_hex(value)),
+                });
+            }
+        }
+
+        // stable, so assignments to the same cell stay in order
+        entries.sort_by_key(|e| (e.row, e.kind, e.column));
+        Witness { entries }
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Csv => self.to_csv(),
+            Format::Json => self.to_json(),
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("row,kind,column,region,name,value\n");
+        for entry in &self.entries {
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                entry.row,
+                entry.kind,
+                entry.column,
+                csv_field(entry.region.as_deref().unwrap_or("")),
+                csv_field(&entry.name),
+                entry.value.as_deref().unwrap_or("")
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("witness serializes")
+    }
+}
+
+// quoted when it holds a separator, a quote or a line break, quotes doubled
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    use super::{csv_field, CellKind, Witness};
+    use crate::{circuits, example4, field::fp_to_hex, test_fibonacci};
+
+    #[test]
+    fn test_add() {
+        let (a, b) = (Fp::from(2), Fp::from(3));
+        let circuit = example4::AddCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        let witness = Witness::of(&circuit, &[vec![a, b, a + b]]).unwrap();
+
+        let row0: Vec<_> = witness
+            .entries
+            .iter()
+            .filter(|e| e.row == 0)
+            .map(|e| (e.kind, e.column, e.value.clone()))
+            .collect();
+        let hex = |v: u64| Some(fp_to_hex(&Fp::from(v)));
+        assert_eq!(
+            row0,
+            vec![
+                (CellKind::Advice, 0, hex(2)),
+                (CellKind::Advice, 1, hex(3)),
+                (CellKind::Advice, 2, hex(5)),
+                (CellKind::Instance, 0, hex(2)),
+                (CellKind::Selector, 0, hex(1)),
+            ]
+        );
+        assert!(witness.entries[..3]
+            .iter()
+            .all(|e| e.region.as_deref() == Some("add region")));
+
+        let csv = witness.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("row,kind,column,region,name,value"));
+        assert_eq!(csv.lines().count(), witness.entries.len() + 1);
+
+        let json: serde_json::Value = serde_json::from_str(&witness.to_json()).unwrap();
+        assert_eq!(json["entries"][0]["kind"], "advice");
+        assert_eq!(json["entries"][0]["region"], "add region");
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        let (a, b) = (Fp::from(1), Fp::from(1));
+        let circuit = test_fibonacci::MyCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        let res = circuits::fibonacci(a, b, 9);
+        let witness = Witness::of(&circuit, &[vec![a, b, res]]).unwrap();
+
+        // the last row of the chip holds the result in its third column
+        let last = witness
+            .entries
+            .iter()
+            .filter(|e| e.kind == CellKind::Advice && e.column == 2)
+            .max_by_key(|e| e.row)
+            .unwrap();
+        assert_eq!(last.value, Some(fp_to_hex(&res)));
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("add region"), "add region");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
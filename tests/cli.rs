@@ -0,0 +1,425 @@
+use std::{fs, process::Command};
+
+#[path = "../src/temp_dir.rs"]
+mod temp_dir;
+
+use temp_dir::TempDir;
+
+fn cli(args: &[&str]) -> (bool, String) {
+    let out = Command::new(env!("CARGO_BIN_EXE_halo2-example"))
+        .args(args)
+        .output()
+        .unwrap();
+    let text =
+        String::from_utf8_lossy(&out.stdout).to_string() + &String::from_utf8_lossy(&out.stderr);
+    (out.status.success(), text)
+}
+
+#[test]
+fn test_list() {
+    let (ok, out) = cli(&["list"]);
+    assert!(ok);
+    for name in ["add", "equality", "add2", "fibonacci"] {
+        assert!(out.contains(name), "{} missing from {}", name, out);
+    }
+}
+
+#[test]
+fn test_cost() {
+    let (ok, out) = cli(&["cost", "--circuit", "add2", "--json"]);
+    assert!(ok, "{}", out);
+    let reports: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(reports[0]["circuit"], "add2");
+
+    assert!(!cli(&["cost", "--circuit", "nope"]).0);
+}
+
+#[test]
+fn test_layout() {
+    let (ok, out) = cli(&["layout", "--circuit", "equality"]);
+    assert!(ok, "{}", out);
+    assert!(
+        out.starts_with("row  |  a0  a1 |  i0 |  s0 | regions"),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains("advice[1] row 0 = instance[0] row 1"),
+        "{}",
+        out
+    );
+
+    let (ok, out) = cli(&["layout", "--circuit", "add2", "--format", "html"]);
+    assert!(ok, "{}", out);
+    assert!(out.contains("<h1>add2 layout</h1>"), "{}", out);
+    assert!(!cli(&["layout", "--circuit", "add2", "--format", "png"]).0);
+}
+
+#[test]
+fn test_inputs_file() {
+    // every sample is named after the circuit it feeds
+    let samples = fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/inputs")).unwrap();
+    for sample in samples {
+        let path = sample.unwrap().path();
+        let circuit = path.file_stem().unwrap().to_str().unwrap().to_string();
+        let path = path.to_str().unwrap();
+
+        let (ok, out) = cli(&["mock", "--circuit", &circuit, "--inputs", path]);
+        assert!(ok, "{}: {}", path, out);
+        let (ok, out) = cli(&["witness", "--circuit", &circuit, "--inputs", path]);
+        assert!(ok, "{}: {}", path, out);
+    }
+
+    let tmp = TempDir::new("cli-inputs");
+    let dir = tmp.path();
+    // the file's instances replace the implied ones
+    let wrong = dir.join("wrong.json");
+    fs::write(
+        &wrong,
+        r#"{"a": 1, "b": 1, "instances": [["1", "1", "56"]]}"#,
+    )
+    .unwrap();
+    let (ok, out) = cli(&[
+        "mock",
+        "--circuit",
+        "fibonacci",
+        "--inputs",
+        wrong.to_str().unwrap(),
+    ]);
+    assert!(!ok);
+    assert!(
+        out.contains("copy constraint not satisfied: instance[0]"),
+        "{}",
+        out
+    );
+
+    // fields of another circuit
+    let add = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/inputs/add.json");
+    let (ok, out) = cli(&["mock", "--circuit", "equality", "--inputs", add]);
+    assert!(!ok);
+    assert!(out.contains("unknown field `a`"), "{}", out);
+
+    let bad = dir.join("bad.toml");
+    fs::write(&bad, "a = 1\nb = \"0x\"\nc = 3\n").unwrap();
+    let (ok, out) = cli(&[
+        "mock",
+        "--circuit",
+        "add2",
+        "--inputs",
+        bad.to_str().unwrap(),
+    ]);
+    assert!(!ok);
+    assert!(out.contains("invalid toml input"), "{}", out);
+}
+
+#[test]
+fn test_witness() {
+    let (ok, out) = cli(&["witness", "--circuit", "add", "-i", "a=2", "-i", "b=3"]);
+    assert!(ok, "{}", out);
+    assert!(
+        out.starts_with("row,kind,column,region,name,value\n0,advice,0,add region,"),
+        "{}",
+        out
+    );
+
+    let tmp = TempDir::new("cli-witness");
+    let dir = tmp.path();
+    let path = dir.join("witness.json");
+    let (ok, out) = cli(&[
+        "witness",
+        "--circuit",
+        "fibonacci",
+        "-i",
+        "a=1",
+        "-i",
+        "b=1",
+        "--format",
+        "json",
+        "--out",
+        path.to_str().unwrap(),
+    ]);
+    assert!(ok, "{}", out);
+    let witness: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(!witness["entries"].as_array().unwrap().is_empty());
+
+    assert!(!cli(&["witness", "--circuit", "add", "-i", "a=2"]).0);
+}
+
+#[test]
+fn test_gates() {
+    let (ok, out) = cli(&["gates", "--circuit", "add2"]);
+    assert!(ok, "{}", out);
+    assert!(
+        out.contains("s0 * (a0[cur] + a1[cur] + a2[next] - a3[next])  (degree 2)"),
+        "{}",
+        out
+    );
+}
+
+#[cfg(feature = "dot-graph")]
+#[test]
+fn test_graph() {
+    let (ok, out) = cli(&["graph", "--circuit", "add2"]);
+    assert!(ok, "{}", out);
+    assert!(out.starts_with("digraph gates"), "{}", out);
+    assert!(out.contains("add 2 constraint"), "{}", out);
+
+    let (ok, out) = cli(&["graph", "--circuit", "add2", "--namespaces"]);
+    assert!(ok, "{}", out);
+    assert!(out.contains("intermediate assign"), "{}", out);
+}
+
+#[test]
+fn test_mock() {
+    assert!(cli(&["mock", "--circuit", "add", "-i", "a=5", "-i", "b=7"]).0);
+    assert!(
+        cli(&[
+            "mock",
+            "--circuit",
+            "fibonacci",
+            "-i",
+            "a=1",
+            "-i",
+            "b=0x01"
+        ])
+        .0
+    );
+
+    let (ok, out) = cli(&[
+        "mock",
+        "--circuit",
+        "equality",
+        "-i",
+        "input=1",
+        "-i",
+        "output=2",
+    ]);
+    assert!(!ok);
+    assert!(out.contains("constraint not satisfied"), "{}", out);
+    assert!(out.contains("equal gate"), "{}", out);
+
+    let (ok, out) = cli(&[
+        "mock",
+        "--circuit",
+        "equality",
+        "-i",
+        "input=1",
+        "-i",
+        "output=2",
+        "--json",
+    ]);
+    assert!(!ok);
+    assert!(
+        out.contains(r#""kind": "constraint_not_satisfied""#),
+        "{}",
+        out
+    );
+
+    let (ok, out) = cli(&["mock", "--circuit", "add", "-i", "a=5"]);
+    assert!(!ok);
+    assert!(out.contains("needs input"), "{}", out);
+}
+
+#[test]
+fn test_setup_prove_verify() {
+    let tmp = TempDir::new("cli-prove");
+    let dir = tmp.path();
+    let dir_arg = dir.to_str().unwrap();
+    let params = dir.join("params-k4.bin");
+    let vk = dir.join("add2-k4.vk");
+    let proof = dir.join("add2.proof");
+    let inputs = dir.join("inputs.json");
+    fs::write(&inputs, r#"{"a": "5", "b": 7, "c": "0x0b"}"#).unwrap();
+
+    let (ok, out) = cli(&["setup", "--circuit", "add2", "--out-dir", dir_arg]);
+    assert!(ok, "{}", out);
+    assert!(params.exists() && vk.exists());
+
+    let (ok, out) = cli(&[
+        "prove",
+        "--circuit",
+        "add2",
+        "--inputs",
+        inputs.to_str().unwrap(),
+        "--params",
+        params.to_str().unwrap(),
+        "--out",
+        proof.to_str().unwrap(),
+    ]);
+    assert!(ok, "{}", out);
+
+    let verify = |circuit: &str, k: &str, extra: &[&str]| {
+        let mut args = vec![
+            "verify",
+            "--proof",
+            proof.to_str().unwrap(),
+            "--circuit",
+            circuit,
+            "--k",
+            k,
+        ];
+        args.extend_from_slice(extra);
+        cli(&args)
+    };
+
+    let (ok, out) = verify(
+        "add2",
+        "4",
+        &[
+            "--params",
+            params.to_str().unwrap(),
+            "--vk",
+            vk.to_str().unwrap(),
+        ],
+    );
+    assert!(ok, "{}", out);
+    assert!(out.contains("valid"));
+
+    // the verifier picks the circuit and k, whatever the envelope claims
+    let (ok, out) = verify("add", "4", &[]);
+    assert!(!ok);
+    assert!(out.contains("expected \"add\""), "{}", out);
+    let (ok, out) = verify("add2", "5", &[]);
+    assert!(!ok);
+    assert!(out.contains("expected k = 5"), "{}", out);
+    let (ok, out) = verify("add2", "5", &["--params", params.to_str().unwrap()]);
+    assert!(!ok);
+    assert!(out.contains("are for k = 4, expected k = 5"), "{}", out);
+
+    // a verifying key of another circuit is rejected
+    let (ok, _) = cli(&["setup", "--circuit", "add", "--out-dir", dir_arg]);
+    assert!(ok);
+    let add_vk = dir.join("add-k4.vk");
+    let (ok, out) = verify("add2", "4", &["--vk", add_vk.to_str().unwrap()]);
+    assert!(!ok);
+    assert!(out.contains("different circuit"), "{}", out);
+}
+
+#[test]
+fn test_json_proof() {
+    let tmp = TempDir::new("cli-json");
+    let dir = tmp.path();
+    let proof = dir.join("equality.json");
+
+    let (ok, out) = cli(&[
+        "prove",
+        "--circuit",
+        "equality",
+        "-i",
+        "input=9",
+        "-i",
+        "output=9",
+        "--out",
+        proof.to_str().unwrap(),
+        "--json",
+    ]);
+    assert!(ok, "{}", out);
+    assert!(fs::read_to_string(&proof).unwrap().contains("\"equality\""));
+
+    // circuit and k come from the envelope unless given
+    let (ok, out) = cli(&["verify", "--proof", proof.to_str().unwrap()]);
+    assert!(ok, "{}", out);
+    assert!(out.contains("proof for equality is valid"), "{}", out);
+
+    // inputs the circuit rejects get a diagnostic instead of a proof
+    let unequal = dir.join("unequal.json");
+    let (ok, out) = cli(&[
+        "prove",
+        "--circuit",
+        "equality",
+        "-i",
+        "input=1",
+        "-i",
+        "output=2",
+        "--out",
+        unequal.to_str().unwrap(),
+    ]);
+    assert!(!ok);
+    assert!(out.contains("constraint not satisfied"), "{}", out);
+    assert!(out.contains("no proof written"), "{}", out);
+    assert!(!unequal.exists());
+}
+
+#[test]
+fn test_transcripts() {
+    let tmp = TempDir::new("cli-transcripts");
+    let dir = tmp.path();
+
+    for transcript in ["blake2b", "sha256", "poseidon"] {
+        let proof = dir.join(format!("{}.bin", transcript));
+        let (ok, out) = cli(&[
+            "prove",
+            "--circuit",
+            "add",
+            "-i",
+            "a=2",
+            "-i",
+            "b=3",
+            "--transcript",
+            transcript,
+            "--out",
+            proof.to_str().unwrap(),
+        ]);
+        assert!(ok, "{}", out);
+
+        // the envelope names the transcript, verify needs no flag for it
+        let (ok, out) = cli(&["verify", "--proof", proof.to_str().unwrap()]);
+        assert!(ok, "{}: {}", transcript, out);
+    }
+
+    let (ok, out) = cli(&[
+        "prove",
+        "--circuit",
+        "add",
+        "-i",
+        "a=2",
+        "-i",
+        "b=3",
+        "--transcript",
+        "keccak",
+        "--out",
+        dir.join("keccak.bin").to_str().unwrap(),
+    ]);
+    assert!(!ok);
+    assert!(out.contains("unknown transcript"), "{}", out);
+}
+
+// regenerate with `cargo run -- prove --circuit <name> -i a=.. -i b=.. --seed 1 --json
+// --out testdata/proofs/<name>.json` after a deliberate change to the circuit or prover
+#[test]
+fn test_golden_proofs() {
+    let tmp = TempDir::new("cli-golden");
+    let dir = tmp.path();
+
+    for (circuit, inputs) in [("add", ["a=2", "b=3"]), ("fibonacci", ["a=1", "b=1"])] {
+        let proof = dir.join(format!("{}.json", circuit));
+        let (ok, out) = cli(&[
+            "prove",
+            "--circuit",
+            circuit,
+            "-i",
+            inputs[0],
+            "-i",
+            inputs[1],
+            "--seed",
+            "1",
+            "--json",
+            "--out",
+            proof.to_str().unwrap(),
+        ]);
+        assert!(ok, "{}", out);
+
+        let golden = format!(
+            "{}/testdata/proofs/{}.json",
+            env!("CARGO_MANIFEST_DIR"),
+            circuit
+        );
+        assert_eq!(
+            fs::read_to_string(&proof).unwrap(),
+            fs::read_to_string(&golden).unwrap(),
+            "proof bytes of {} changed",
+            circuit
+        );
+    }
+}
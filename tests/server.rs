@@ -0,0 +1,216 @@
+use std::{fs, io::Read, path::PathBuf, sync::Arc, thread};
+
+use halo2_example::{
+    keys::KeyCache,
+    server::{Server, HANDLERS, MAX_BODY},
+};
+use serde_json::{json, Value};
+
+#[path = "../src/temp_dir.rs"]
+mod temp_dir;
+
+use temp_dir::TempDir;
+
+// a server on a free localhost port, stopped when the test is done with it
+struct Local {
+    server: Arc<Server>,
+    url: String,
+    dir: TempDir,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Local {
+    fn start(name: &str) -> Self {
+        let dir = TempDir::new(&format!("server-{}", name));
+        let cache = KeyCache::new(dir.path()).unwrap();
+        let server = Arc::new(Server::bind("127.0.0.1:0", cache, 2).unwrap());
+        let url = format!("http://{}", server.local_addr());
+
+        let running = server.clone();
+        let handle = Some(thread::spawn(move || running.run()));
+        Self {
+            server,
+            url,
+            dir,
+            handle,
+        }
+    }
+
+    fn get(&self, path: &str) -> (u16, Value) {
+        response(ureq::get(&format!("{}{}", self.url, path)).call())
+    }
+
+    fn get_bytes(&self, path: &str) -> Vec<u8> {
+        let res = ureq::get(&format!("{}{}", self.url, path)).call().unwrap();
+        assert_eq!(res.content_type(), "application/octet-stream");
+        let mut bytes = vec![];
+        res.into_reader().read_to_end(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn post(&self, path: &str, body: &Value) -> (u16, Value) {
+        response(ureq::post(&format!("{}{}", self.url, path)).send_json(body))
+    }
+}
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        self.server.stop();
+        self.handle.take().unwrap().join().unwrap();
+    }
+}
+
+fn response(res: Result<ureq::Response, ureq::Error>) -> (u16, Value) {
+    let res = match res {
+        Ok(res) => res,
+        Err(ureq::Error::Status(_, res)) => res,
+        Err(e) => panic!("request failed: {}", e),
+    };
+    (res.status(), res.into_json().unwrap())
+}
+
+#[test]
+fn test_list_and_fingerprint() {
+    let local = Local::start("list");
+
+    let (status, circuits) = local.get("/circuits");
+    assert_eq!(status, 200);
+    let names: Vec<&str> = circuits
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["name"].as_str().unwrap())
+        .collect();
+    assert!(names.contains(&"add") && names.contains(&"fibonacci"));
+
+    let vk = local.get_bytes("/circuits/add/fingerprint");
+
+    // the same bytes as the fingerprint file the key cache wrote
+    let files: Vec<PathBuf> = fs::read_dir(local.dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("vk".as_ref()))
+        .collect();
+    assert_eq!(files.len(), 1);
+    assert_eq!(vk, fs::read(&files[0]).unwrap());
+    assert!(vk.starts_with(b"H2VK"));
+
+    assert_eq!(local.get("/circuits/nope/fingerprint").0, 404);
+    assert_eq!(local.get("/nope").0, 404);
+    assert_eq!(local.post("/circuits", &json!({})).0, 405);
+}
+
+#[test]
+fn test_prove_verify() {
+    let local = Local::start("prove");
+
+    let (status, envelope) = local.post(
+        "/prove",
+        &json!({"circuit": "add2", "inputs": {"a": 1, "b": "2", "c": "0x03"}}),
+    );
+    assert_eq!(status, 200, "{}", envelope);
+    assert_eq!(envelope["circuit"], "add2");
+
+    // the magic followed by the fingerprint
+    let vk = local.get_bytes("/circuits/add2/fingerprint");
+    assert_eq!(envelope["vk_fingerprint"], hex::encode(&vk[4..]));
+
+    let (status, res) = local.post("/verify", &envelope);
+    assert_eq!(status, 200);
+    assert_eq!(res["valid"], true, "{}", res);
+
+    // claim a different sum
+    let mut forged = envelope.clone();
+    forged["instances"][0][0][3] = json!(format!("0x{:064x}", 7));
+    let (status, res) = local.post("/verify", &forged);
+    assert_eq!(status, 200);
+    assert_eq!(res["valid"], false);
+    assert!(res["error"].is_string());
+}
+
+#[test]
+fn test_prove_transcript() {
+    let local = Local::start("transcript");
+
+    let (status, envelope) = local.post(
+        "/prove",
+        &json!({"circuit": "add", "inputs": {"a": 1, "b": 2}, "transcript": "poseidon"}),
+    );
+    assert_eq!(status, 200, "{}", envelope);
+    assert_eq!(envelope["transcript"], "poseidon");
+
+    let (status, res) = local.post("/verify", &envelope);
+    assert_eq!(status, 200);
+    assert_eq!(res["valid"], true, "{}", res);
+
+    // the proof does not verify when read with another transcript
+    let mut relabeled = envelope.clone();
+    relabeled["transcript"] = json!("blake2b");
+    let (_, res) = local.post("/verify", &relabeled);
+    assert_eq!(res["valid"], false);
+
+    let (status, _) = local.post(
+        "/prove",
+        &json!({"circuit": "add", "inputs": {"a": 1, "b": 2}, "transcript": "keccak"}),
+    );
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn test_body_limit() {
+    let local = Local::start("limit");
+
+    let body = " ".repeat(MAX_BODY as usize + 1);
+    let res = ureq::post(&format!("{}/verify", local.url)).send_string(&body);
+    let (status, res) = response(res);
+    assert_eq!(status, 413);
+    assert!(res["error"].as_str().unwrap().contains("over"), "{}", res);
+
+    // right at the limit the body is read and parsed
+    let body = " ".repeat(MAX_BODY as usize);
+    let (status, _) = response(ureq::post(&format!("{}/verify", local.url)).send_string(&body));
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn test_prove_errors() {
+    let local = Local::start("errors");
+
+    let (status, res) = local.post("/prove", &json!({"circuit": "add", "inputs": {"a": 1}}));
+    assert_eq!(status, 400);
+    assert!(res["error"].as_str().unwrap().contains("missing field `b`"));
+
+    let (status, _) = local.post("/prove", &json!({"circuit": "nope", "inputs": {}}));
+    assert_eq!(status, 404);
+
+    // instances that do not follow from the inputs
+    let (status, _) = local.post(
+        "/prove",
+        &json!({"circuit": "add", "inputs": {"a": 1, "b": 2}, "instances": [["1", "2", "4"]]}),
+    );
+    assert_eq!(status, 400);
+
+    // inputs the circuit rejects
+    let (status, res) = local.post(
+        "/prove",
+        &json!({"circuit": "equality", "inputs": {"input": 1, "output": 2}}),
+    );
+    assert_eq!(status, 400);
+    assert!(res["error"].as_str().unwrap().contains("do not satisfy"));
+
+    let (status, _) = local.post("/verify", &json!({"proof": "zz"}));
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn test_concurrent() {
+    let local = Local::start("concurrent");
+
+    // more clients than handlers, the rest wait their turn
+    thread::scope(|scope| {
+        for _ in 0..HANDLERS * 3 {
+            scope.spawn(|| assert_eq!(local.get("/circuits").0, 200));
+        }
+    });
+    // dropping `local` stops every handler, or the join hangs
+}